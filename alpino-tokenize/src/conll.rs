@@ -1,19 +1,25 @@
-use std::fs::File;
-use std::io::{BufRead, BufReader, BufWriter};
+use std::io::BufWriter;
+use std::process;
 
-use alpino_tokenizer::{AlpinoTokenizer, Tokenizer};
+use alpino_tokenizer::AlpinoTokenizer;
 use clap::{App, Arg, ArgMatches};
 use conllu::io::{WriteSentence, Writer};
-use lazy_static::lazy_static;
-use regex::Regex;
 use stdinout::{Input, OrExit, Output};
 use udgraph::graph::{Comment, Sentence};
-use udgraph::token::TokenBuilder;
+use udgraph::token::{Misc, TokenBuilder};
 
+use crate::paragraphs::{Paragraph, Paragraphs};
+use crate::parallel::tokenize_paragraphs;
 use crate::TokenizeApp;
 
 // Option constants
 static IDENTIFIERS: &str = "IDENTIFIERS";
+static MARKERS: &str = "MARKERS";
+static NO_SPLIT: &str = "NO_SPLIT";
+static RECOVER: &str = "RECOVER";
+static SENTENCE_PER_LINE: &str = "SENTENCE_PER_LINE";
+static TEXT: &str = "TEXT";
+static THREADS: &str = "THREADS";
 static WIKIPEDIA: &str = "WIKIPEDIA";
 
 // Argument constants
@@ -21,72 +27,106 @@ static INPUT: &str = "INPUT";
 static OUTPUT: &str = "OUTPUT";
 static PROTOBUF: &str = "PROTOBUF";
 
-// Expressions
-lazy_static! {
-    static ref WIKIPEDIA_DOC_EXPR: Regex =
-        Regex::new("<doc.+id=\"([^\"]+)\".+title=\"([^\"]+)\"").unwrap();
-}
-
 pub struct ConlluApp {
     input_filename: Option<String>,
     output_filename: Option<String>,
     protobuf_filename: String,
     identifiers: bool,
+    markers: bool,
+    no_split: bool,
+    recover: bool,
+    sentence_per_line: bool,
+    text: bool,
+    threads: usize,
     wikipedia: bool,
 }
 
+/// Tokenized paragraph.
+struct TokenizedParagraph {
+    sentences: Vec<Sentence>,
+
+    /// Messages about recoveries from rejected text.
+    recoveries: Vec<String>,
+}
+
 impl ConlluApp {
     fn tokenize_para(
         &self,
         tokenizer: &AlpinoTokenizer,
-        lines: &[String],
-        writer: &mut impl WriteSentence,
-        doc_id: Option<&String>,
-        doc_title: Option<&String>,
-        para_id: &mut usize,
-    ) {
-        if lines.is_empty() {
-            return;
-        }
+        paragraph: &Paragraph,
+    ) -> Result<TokenizedParagraph, String> {
+        let tokenized = paragraph.tokenize(tokenizer, self.recover, self.sentence_per_line)?;
+        let recoveries = tokenized
+            .recoveries
+            .iter()
+            .map(|recovery| paragraph.recovery_message(recovery))
+            .collect();
+        let text = &paragraph.text;
 
-        let text = lines.join(" ");
-        let tokenized = tokenizer
-            .tokenize(&text)
-            .or_exit("Cannot tokenize paragraph", 1);
+        let para_identifier = match &paragraph.doc_id {
+            Some(doc_id) => format!("d.{}.p.{}", doc_id, paragraph.para_id),
+            None => format!("p.{}", paragraph.para_id),
+        };
 
-        for (sent_id, sent) in tokenized.into_iter().enumerate() {
+        let mut sentences = Vec::with_capacity(tokenized.sentences.len());
+        for (sent_id, sent) in tokenized.sentences.into_iter().enumerate() {
+            let span = sent.span();
             let mut graph = sent
+                .into_tokens()
                 .into_iter()
-                .map(|t| TokenBuilder::new(t).into())
+                .map(|t| {
+                    let mut token = TokenBuilder::new(t.text());
+                    if !t.space_after() {
+                        token = token.misc(Misc::from_iter([("SpaceAfter", Some("No"))]));
+                    }
+                    token.into()
+                })
                 .collect::<Sentence>();
 
+            let comments = graph.comments_mut();
+
+            if self.markers && sent_id == 0 {
+                if let (Some(doc_id), 0) = (&paragraph.doc_id, paragraph.para_id) {
+                    comments.push(Comment::AttrVal {
+                        attr: "newdoc id".to_string(),
+                        val: doc_id.clone(),
+                    });
+                }
+
+                comments.push(Comment::AttrVal {
+                    attr: "newpar id".to_string(),
+                    val: para_identifier.clone(),
+                });
+            }
+
             if self.identifiers {
-                if let Some(doc_title) = doc_title {
-                    graph.comments_mut().push(Comment::AttrVal {
+                if let Some(doc_title) = &paragraph.doc_title {
+                    comments.push(Comment::AttrVal {
                         attr: "title".to_string(),
                         val: doc_title.clone(),
                     });
                 }
 
-                if let Some(doc_id) = doc_id {
-                    graph.comments_mut().push(Comment::AttrVal {
-                        attr: "sent_id".to_string(),
-                        val: format!("d.{}.p.{}.s.{}", doc_id, para_id, sent_id),
-                    })
-                } else {
-                    graph.comments_mut().push(Comment::AttrVal {
-                        attr: "sent_id".to_string(),
-                        val: format!("p.{}.s.{}", para_id, sent_id),
-                    })
-                };
+                comments.push(Comment::AttrVal {
+                    attr: "sent_id".to_string(),
+                    val: format!("{}.s.{}", para_identifier, sent_id),
+                });
+            }
+
+            if self.text {
+                comments.push(Comment::AttrVal {
+                    attr: "text".to_string(),
+                    val: text[span.bytes()].to_string(),
+                });
             }
 
-            writer
-                .write_sentence(&graph)
-                .or_exit("Cannot write sentence", 1);
+            sentences.push(graph);
         }
 
-        *para_id += 1;
+        Ok(TokenizedParagraph {
+            sentences,
+            recoveries,
+        })
     }
 }
 
@@ -96,7 +136,7 @@ impl TokenizeApp for ConlluApp {
             .about("Tokenize input and output as CoNLL-X")
             .arg(
                 Arg::with_name(PROTOBUF)
-                    .help("Tokenizer protobuf")
+                    .help("Tokenizer protobuf or compact transducer")
                     .required(true)
                     .index(1),
             )
@@ -107,6 +147,38 @@ impl TokenizeApp for ConlluApp {
                     .short('i')
                     .help("Add paragraph/sentence identifiers"),
             )
+            .arg(
+                Arg::with_name(MARKERS)
+                    .long("markers")
+                    .help("Add newdoc/newpar comments at document/paragraph boundaries"),
+            )
+            .arg(
+                Arg::with_name(NO_SPLIT)
+                    .long("no-split")
+                    .help("Tokenize every paragraph as a single sentence"),
+            )
+            .arg(
+                Arg::with_name(RECOVER)
+                    .long("recover")
+                    .help("Recover from text that cannot be tokenized"),
+            )
+            .arg(
+                Arg::with_name(TEXT)
+                    .long("text")
+                    .help("Add comments with the original sentence text"),
+            )
+            .arg(
+                Arg::with_name(SENTENCE_PER_LINE)
+                    .long("sentence-per-line")
+                    .help("Tokenize every line as a single sentence"),
+            )
+            .arg(
+                Arg::with_name(THREADS)
+                    .long("threads")
+                    .takes_value(true)
+                    .default_value("1")
+                    .help("Number of tokenizer threads, 0 for the available parallelism"),
+            )
             .arg(
                 Arg::with_name(WIKIPEDIA)
                     .long("wikipedia")
@@ -123,24 +195,37 @@ impl TokenizeApp for ConlluApp {
             .to_owned();
 
         let identifiers = matches.is_present(IDENTIFIERS);
+        let markers = matches.is_present(MARKERS);
+        let no_split = matches.is_present(NO_SPLIT);
+        let recover = matches.is_present(RECOVER);
+        let sentence_per_line = matches.is_present(SENTENCE_PER_LINE);
+        let text = matches.is_present(TEXT);
         let wikipedia = matches.is_present(WIKIPEDIA);
+        let threads = matches
+            .value_of(THREADS)
+            .unwrap()
+            .parse::<usize>()
+            .or_exit("Cannot parse number of threads", 1);
 
         ConlluApp {
             input_filename,
             output_filename,
             protobuf_filename,
             identifiers,
+            markers,
+            no_split,
+            recover,
+            sentence_per_line,
+            text,
+            threads,
             wikipedia,
         }
     }
 
     fn run(&self) {
-        let protobuf = BufReader::new(
-            File::open(&self.protobuf_filename)
-                .or_exit("Cannot open tokenizer protobuf definition", 1),
-        );
-        let tokenizer =
-            AlpinoTokenizer::from_buf_read(protobuf).or_exit("Cannot load tokenizer", 1);
+        let tokenizer = AlpinoTokenizer::from_file(&self.protobuf_filename)
+            .or_exit("Cannot load tokenizer", 1)
+            .with_sentence_splitting(!(self.no_split || self.sentence_per_line));
 
         let input = Input::from(self.input_filename.as_ref());
         let reader = input.buf_read().or_exit("Cannot open input", 1);
@@ -150,50 +235,26 @@ impl TokenizeApp for ConlluApp {
             output.write().or_exit("Cannot open output", 1),
         ));
 
-        let mut para = vec![];
-        let mut para_id = 0;
-        let mut doc_id = None;
-        let mut doc_title = None;
-
-        for line in reader.lines() {
-            let line = line.or_exit("Cannot read line", 1);
-
-            if line.trim().is_empty() {
-                self.tokenize_para(
-                    &tokenizer,
-                    &para,
-                    &mut writer,
-                    doc_id.as_ref(),
-                    doc_title.as_ref(),
-                    &mut para_id,
-                );
-                para.clear();
-            } else if self.wikipedia {
-                if line.starts_with("<doc") {
-                    match WIKIPEDIA_DOC_EXPR.captures(&line) {
-                        Some(captures) => {
-                            doc_id = Some(captures.get(1).unwrap().as_str().to_owned());
-                            doc_title = Some(captures.get(2).unwrap().as_str().to_owned());
-                        }
-                        None => eprintln!("Could not read identifier in doc tag: {}", line),
-                    }
+        tokenize_paragraphs(
+            Paragraphs::new(reader, self.wikipedia),
+            self.threads,
+            |paragraph| self.tokenize_para(&tokenizer, paragraph),
+            |paragraph| {
+                let paragraph = paragraph.unwrap_or_else(|err| {
+                    eprintln!("{}", err);
+                    process::exit(1)
+                });
 
-                    para_id = 0;
-                } else if !line.starts_with("</doc") {
-                    para.push(line);
+                for recovery in paragraph.recoveries {
+                    eprintln!("{}", recovery);
                 }
-            } else {
-                para.push(line);
-            }
-        }
 
-        self.tokenize_para(
-            &tokenizer,
-            &para,
-            &mut writer,
-            doc_id.as_ref(),
-            doc_title.as_ref(),
-            &mut para_id,
+                for sentence in &paragraph.sentences {
+                    writer
+                        .write_sentence(sentence)
+                        .or_exit("Cannot write sentence", 1);
+                }
+            },
         );
     }
 }
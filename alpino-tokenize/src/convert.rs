@@ -0,0 +1,92 @@
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+
+use alpino_tokenizer::FiniteStateTokenizer;
+use clap::{App, Arg, ArgMatches};
+use stdinout::{OrExit, Output};
+
+use crate::TokenizeApp;
+
+// Argument constants
+static INPUT_FORMAT: &str = "INPUT_FORMAT";
+static OUTPUT: &str = "OUTPUT";
+static OUTPUT_FORMAT: &str = "OUTPUT_FORMAT";
+static PROTOBUF: &str = "PROTOBUF";
+
+pub struct ConvertApp {
+    input_format: String,
+    output_filename: Option<String>,
+    output_format: String,
+    protobuf_filename: String,
+}
+
+impl TokenizeApp for ConvertApp {
+    fn app() -> App<'static> {
+        App::new("convert")
+            .about("Convert a tokenizer transducer to the compact or protobuf format")
+            .arg(
+                Arg::with_name(PROTOBUF)
+                    .help("Tokenizer protobuf, AT&T, or FSA utilities transducer")
+                    .required(true)
+                    .index(1),
+            )
+            .arg(Arg::with_name(OUTPUT).help("Output transducer").index(2))
+            .arg(
+                Arg::with_name(INPUT_FORMAT)
+                    .long("input-format")
+                    .takes_value(true)
+                    .possible_values(["protobuf", "att", "fsa-prolog"])
+                    .default_value("protobuf")
+                    .help("Input transducer format"),
+            )
+            .arg(
+                Arg::with_name(OUTPUT_FORMAT)
+                    .long("output-format")
+                    .takes_value(true)
+                    .possible_values(["compact", "protobuf"])
+                    .default_value("compact")
+                    .help("Output transducer format"),
+            )
+    }
+
+    fn parse(matches: &ArgMatches) -> Self {
+        let output_filename = matches.value_of(OUTPUT).map(ToOwned::to_owned);
+        let protobuf_filename = matches
+            .value_of(PROTOBUF)
+            .expect("Protobuf filename must be specified")
+            .to_owned();
+
+        let input_format = matches.value_of(INPUT_FORMAT).unwrap().to_owned();
+        let output_format = matches.value_of(OUTPUT_FORMAT).unwrap().to_owned();
+
+        ConvertApp {
+            input_format,
+            output_filename,
+            output_format,
+            protobuf_filename,
+        }
+    }
+
+    fn run(&self) {
+        let read = BufReader::new(
+            File::open(&self.protobuf_filename).or_exit("Cannot open tokenizer transducer", 1),
+        );
+        let tokenizer = match self.input_format.as_str() {
+            "protobuf" => FiniteStateTokenizer::from_buf_read(read),
+            "att" => FiniteStateTokenizer::from_att(read),
+            "fsa-prolog" => FiniteStateTokenizer::from_fsa_prolog(read),
+            _ => unreachable!(),
+        }
+        .or_exit("Cannot load tokenizer", 1);
+
+        let output = Output::from(self.output_filename.as_ref());
+        let writer = BufWriter::new(output.write().or_exit("Cannot open output", 1));
+
+        match self.output_format.as_str() {
+            "compact" => tokenizer.write_compact(writer),
+            "protobuf" => tokenizer.write_proto(writer),
+            _ => unreachable!(),
+        }
+        .or_exit("Cannot write transducer", 1);
+    }
+}
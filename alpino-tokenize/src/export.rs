@@ -0,0 +1,95 @@
+use std::io::BufWriter;
+
+use alpino_tokenizer::{ExportFormat, FiniteStateTokenizer, Subgraph};
+use clap::{App, Arg, ArgMatches};
+use stdinout::{OrExit, Output};
+
+use crate::TokenizeApp;
+
+// Argument constants
+static DEPTH: &str = "DEPTH";
+static FORMAT: &str = "FORMAT";
+static OUTPUT: &str = "OUTPUT";
+static PROTOBUF: &str = "PROTOBUF";
+static STATE: &str = "STATE";
+
+pub struct ExportApp {
+    format: ExportFormat,
+    output_filename: Option<String>,
+    protobuf_filename: String,
+    subgraph: Subgraph,
+}
+
+impl TokenizeApp for ExportApp {
+    fn app() -> App<'static> {
+        App::new("export")
+            .about("Export a tokenizer transducer as AT&T or Graphviz DOT")
+            .arg(
+                Arg::with_name(PROTOBUF)
+                    .help("Tokenizer protobuf or compact transducer")
+                    .required(true)
+                    .index(1),
+            )
+            .arg(Arg::with_name(OUTPUT).help("Output file").index(2))
+            .arg(
+                Arg::with_name(FORMAT)
+                    .long("format")
+                    .takes_value(true)
+                    .possible_values(["att", "dot"])
+                    .default_value("dot")
+                    .help("Output format"),
+            )
+            .arg(
+                Arg::with_name(STATE)
+                    .long("state")
+                    .takes_value(true)
+                    .help("Export the subgraph starting at this state"),
+            )
+            .arg(
+                Arg::with_name(DEPTH)
+                    .long("depth")
+                    .takes_value(true)
+                    .help("Maximum number of transitions to follow"),
+            )
+    }
+
+    fn parse(matches: &ArgMatches) -> Self {
+        let output_filename = matches.value_of(OUTPUT).map(ToOwned::to_owned);
+        let protobuf_filename = matches
+            .value_of(PROTOBUF)
+            .expect("Protobuf filename must be specified")
+            .to_owned();
+
+        let format = match matches.value_of(FORMAT).unwrap() {
+            "att" => ExportFormat::Att,
+            "dot" => ExportFormat::Dot,
+            _ => unreachable!(),
+        };
+
+        let state = matches
+            .value_of(STATE)
+            .map(|v| v.parse().or_exit("Cannot parse state", 1));
+        let depth = matches
+            .value_of(DEPTH)
+            .map(|v| v.parse().or_exit("Cannot parse depth", 1));
+
+        ExportApp {
+            format,
+            output_filename,
+            protobuf_filename,
+            subgraph: Subgraph { state, depth },
+        }
+    }
+
+    fn run(&self) {
+        let tokenizer = FiniteStateTokenizer::from_file(&self.protobuf_filename)
+            .or_exit("Cannot load tokenizer", 1);
+
+        let output = Output::from(self.output_filename.as_ref());
+        let writer = BufWriter::new(output.write().or_exit("Cannot open output", 1));
+
+        tokenizer
+            .export(writer, self.format, self.subgraph)
+            .or_exit("Cannot export transducer", 1);
+    }
+}
@@ -0,0 +1,248 @@
+use std::io::{BufWriter, Write};
+use std::process;
+
+use alpino_tokenizer::{AlpinoTokenizer, Sentence};
+use clap::{App, Arg, ArgMatches};
+use serde::Serialize;
+use stdinout::{Input, OrExit, Output};
+
+use crate::paragraphs::{Paragraph, Paragraphs};
+use crate::parallel::tokenize_paragraphs;
+use crate::TokenizeApp;
+
+// Option constants
+static PARAGRAPHS: &str = "PARAGRAPHS";
+static NO_SPLIT: &str = "NO_SPLIT";
+static RECOVER: &str = "RECOVER";
+static SENTENCE_PER_LINE: &str = "SENTENCE_PER_LINE";
+static THREADS: &str = "THREADS";
+static WIKIPEDIA: &str = "WIKIPEDIA";
+
+// Argument constants
+static INPUT: &str = "INPUT";
+static OUTPUT: &str = "OUTPUT";
+static PROTOBUF: &str = "PROTOBUF";
+
+pub struct JsonlApp {
+    input_filename: Option<String>,
+    output_filename: Option<String>,
+    protobuf_filename: String,
+    paragraphs: bool,
+    no_split: bool,
+    recover: bool,
+    sentence_per_line: bool,
+    threads: usize,
+    wikipedia: bool,
+}
+
+/// JSON object for a paragraph.
+#[derive(Serialize)]
+struct JsonParagraph<'a> {
+    doc_id: Option<&'a str>,
+    para_id: usize,
+    text: &'a str,
+    sentences: Vec<JsonSentence<'a>>,
+}
+
+/// JSON object for a sentence that is written on its own line.
+#[derive(Serialize)]
+struct JsonSentenceLine<'a> {
+    doc_id: Option<&'a str>,
+    para_id: usize,
+    #[serde(flatten)]
+    sentence: JsonSentence<'a>,
+}
+
+/// JSON object for a sentence.
+#[derive(Serialize)]
+struct JsonSentence<'a> {
+    sent_id: usize,
+    text: &'a str,
+    tokens: Vec<&'a str>,
+    /// Character offsets of the tokens in the paragraph.
+    offsets: Vec<(usize, usize)>,
+}
+
+/// Tokenized paragraph.
+struct TokenizedParagraph {
+    /// JSON objects, one per line.
+    lines: Vec<String>,
+
+    /// Messages about recoveries from rejected text.
+    recoveries: Vec<String>,
+}
+
+impl JsonlApp {
+    fn tokenize_para(
+        &self,
+        tokenizer: &AlpinoTokenizer,
+        paragraph: &Paragraph,
+    ) -> Result<TokenizedParagraph, String> {
+        let tokenized = paragraph.tokenize(tokenizer, self.recover, self.sentence_per_line)?;
+        let recoveries = tokenized
+            .recoveries
+            .iter()
+            .map(|recovery| paragraph.recovery_message(recovery))
+            .collect();
+        let text = &paragraph.text;
+
+        let sentences = tokenized
+            .sentences
+            .iter()
+            .enumerate()
+            .map(|(sent_id, sent)| json_sentence(paragraph, sent_id, sent));
+
+        let lines = if self.paragraphs {
+            let json = JsonParagraph {
+                doc_id: paragraph.doc_id.as_deref(),
+                para_id: paragraph.para_id,
+                text,
+                sentences: sentences.collect(),
+            };
+            vec![serde_json::to_string(&json).map_err(|err| err.to_string())?]
+        } else {
+            sentences
+                .map(|sentence| {
+                    serde_json::to_string(&JsonSentenceLine {
+                        doc_id: paragraph.doc_id.as_deref(),
+                        para_id: paragraph.para_id,
+                        sentence,
+                    })
+                })
+                .collect::<Result<_, _>>()
+                .map_err(|err| err.to_string())?
+        };
+
+        Ok(TokenizedParagraph { lines, recoveries })
+    }
+}
+
+fn json_sentence<'a>(
+    paragraph: &'a Paragraph,
+    sent_id: usize,
+    sent: &'a Sentence,
+) -> JsonSentence<'a> {
+    JsonSentence {
+        sent_id,
+        text: &paragraph.text[sent.span().bytes()],
+        tokens: sent.tokens().iter().map(|token| token.text()).collect(),
+        offsets: sent
+            .tokens()
+            .iter()
+            .map(|token| (token.span().char_start, token.span().char_end))
+            .collect(),
+    }
+}
+
+impl TokenizeApp for JsonlApp {
+    fn app() -> App<'static> {
+        App::new("jsonl")
+            .about("Tokenize input and output as JSON Lines")
+            .arg(
+                Arg::with_name(PROTOBUF)
+                    .help("Tokenizer protobuf or compact transducer")
+                    .required(true)
+                    .index(1),
+            )
+            .arg(Arg::with_name(INPUT).help("Input corpus").index(2))
+            .arg(Arg::with_name(OUTPUT).help("Output JSON Lines").index(3))
+            .arg(
+                Arg::with_name(PARAGRAPHS)
+                    .long("paragraphs")
+                    .help("Write an object per paragraph rather than per sentence"),
+            )
+            .arg(
+                Arg::with_name(NO_SPLIT)
+                    .long("no-split")
+                    .help("Tokenize every paragraph as a single sentence"),
+            )
+            .arg(
+                Arg::with_name(RECOVER)
+                    .long("recover")
+                    .help("Recover from text that cannot be tokenized"),
+            )
+            .arg(
+                Arg::with_name(SENTENCE_PER_LINE)
+                    .long("sentence-per-line")
+                    .help("Tokenize every line as a single sentence"),
+            )
+            .arg(
+                Arg::with_name(THREADS)
+                    .long("threads")
+                    .takes_value(true)
+                    .default_value("1")
+                    .help("Number of tokenizer threads, 0 for the available parallelism"),
+            )
+            .arg(
+                Arg::with_name(WIKIPEDIA)
+                    .long("wikipedia")
+                    .help("Process wikiextractor output"),
+            )
+    }
+
+    fn parse(matches: &ArgMatches) -> Self {
+        let input_filename = matches.value_of(INPUT).map(ToOwned::to_owned);
+        let output_filename = matches.value_of(OUTPUT).map(ToOwned::to_owned);
+        let protobuf_filename = matches
+            .value_of(PROTOBUF)
+            .expect("Protobuf filename must be specified")
+            .to_owned();
+
+        let paragraphs = matches.is_present(PARAGRAPHS);
+        let no_split = matches.is_present(NO_SPLIT);
+        let recover = matches.is_present(RECOVER);
+        let sentence_per_line = matches.is_present(SENTENCE_PER_LINE);
+        let wikipedia = matches.is_present(WIKIPEDIA);
+        let threads = matches
+            .value_of(THREADS)
+            .unwrap()
+            .parse::<usize>()
+            .or_exit("Cannot parse number of threads", 1);
+
+        JsonlApp {
+            input_filename,
+            output_filename,
+            protobuf_filename,
+            paragraphs,
+            no_split,
+            recover,
+            sentence_per_line,
+            threads,
+            wikipedia,
+        }
+    }
+
+    fn run(&self) {
+        let tokenizer = AlpinoTokenizer::from_file(&self.protobuf_filename)
+            .or_exit("Cannot load tokenizer", 1)
+            .with_sentence_splitting(!(self.no_split || self.sentence_per_line));
+
+        let input = Input::from(self.input_filename.as_ref());
+        let reader = input.buf_read().or_exit("Cannot open input", 1);
+
+        let output = Output::from(self.output_filename.as_ref());
+        let mut writer = BufWriter::new(output.write().or_exit("Cannot open output", 1));
+
+        tokenize_paragraphs(
+            Paragraphs::new(reader, self.wikipedia),
+            self.threads,
+            |paragraph| self.tokenize_para(&tokenizer, paragraph),
+            |paragraph| {
+                let paragraph = paragraph.unwrap_or_else(|err| {
+                    eprintln!("{}", err);
+                    process::exit(1)
+                });
+
+                for recovery in paragraph.recoveries {
+                    eprintln!("{}", recovery);
+                }
+
+                for line in paragraph.lines {
+                    writeln!(writer, "{}", line).or_exit("Cannot write line", 1);
+                }
+            },
+        );
+
+        writer.flush().or_exit("Cannot flush output", 1);
+    }
+}
@@ -4,6 +4,20 @@ use clap::{builder::EnumValueParser, App, AppSettings, Arg, SubCommand};
 
 mod conll;
 
+mod convert;
+
+mod export;
+
+mod jsonl;
+
+mod paragraphs;
+
+mod parallel;
+
+mod sentences;
+
+mod text;
+
 mod traits;
 use clap_complete::{generate, Shell};
 pub use traits::TokenizeApp;
@@ -15,7 +29,14 @@ static DEFAULT_CLAP_SETTINGS: &[AppSettings] = &[
 ];
 
 fn main() {
-    let apps = vec![conll::ConlluApp::app()];
+    let apps = vec![
+        conll::ConlluApp::app(),
+        convert::ConvertApp::app(),
+        export::ExportApp::app(),
+        jsonl::JsonlApp::app(),
+        sentences::SentencesApp::app(),
+        text::TextApp::app(),
+    ];
 
     let cli = App::new("finalfusion")
         .settings(DEFAULT_CLAP_SETTINGS)
@@ -39,6 +60,15 @@ fn main() {
         }
 
         "conllu" => conll::ConlluApp::parse(matches.subcommand_matches("conllu").unwrap()).run(),
+        "convert" => {
+            convert::ConvertApp::parse(matches.subcommand_matches("convert").unwrap()).run()
+        }
+        "export" => export::ExportApp::parse(matches.subcommand_matches("export").unwrap()).run(),
+        "jsonl" => jsonl::JsonlApp::parse(matches.subcommand_matches("jsonl").unwrap()).run(),
+        "sentences" => {
+            sentences::SentencesApp::parse(matches.subcommand_matches("sentences").unwrap()).run()
+        }
+        "text" => text::TextApp::parse(matches.subcommand_matches("text").unwrap()).run(),
         _unknown => unreachable!(),
     }
 }
@@ -0,0 +1,257 @@
+use std::io::{self, BufRead, Lines};
+
+use alpino_tokenizer::{AlpinoTokenizer, Recovery, Sentence, Span, Token, Tokenizer};
+use lazy_static::lazy_static;
+use regex::Regex;
+
+// Expressions
+lazy_static! {
+    static ref WIKIPEDIA_DOC_EXPR: Regex =
+        Regex::new("<doc.+id=\"([^\"]+)\".+title=\"([^\"]+)\"").unwrap();
+}
+
+/// Paragraph of the input.
+pub struct Paragraph {
+    /// The lines of the paragraph, joined by spaces.
+    pub text: String,
+
+    /// Byte offsets of the ends of the lines in `text`.
+    pub line_ends: Vec<usize>,
+
+    /// Identifier of the paragraph within its document.
+    pub para_id: usize,
+
+    /// Identifier of the document (wikiextractor output only).
+    pub doc_id: Option<String>,
+
+    /// Title of the document (wikiextractor output only).
+    pub doc_title: Option<String>,
+}
+
+impl Paragraph {
+    /// Description of the paragraph for messages.
+    pub fn description(&self) -> String {
+        match &self.doc_id {
+            Some(doc_id) => format!("paragraph {} of document {}", self.para_id, doc_id),
+            None => format!("paragraph {}", self.para_id),
+        }
+    }
+
+    /// Sentence-split and tokenize the paragraph.
+    ///
+    /// If `sentence_per_line` is true, every line of the paragraph is
+    /// tokenized as a separate sentence. `tokenizer` should not split
+    /// sentences in that case.
+    pub fn tokenize(
+        &self,
+        tokenizer: &AlpinoTokenizer,
+        recover: bool,
+        sentence_per_line: bool,
+    ) -> Result<TokenizedParagraph, String> {
+        if !sentence_per_line {
+            return self.tokenize_text(tokenizer, recover, &self.text);
+        }
+
+        let mut tokenized = TokenizedParagraph::default();
+        let (mut byte_start, mut char_start) = (0, 0);
+        for &line_end in &self.line_ends {
+            let line = &self.text[byte_start..line_end];
+            let line_tokenized = self.tokenize_text(tokenizer, recover, line)?;
+
+            // Shift the offsets, so that they are relative to the paragraph.
+            tokenized
+                .sentences
+                .extend(line_tokenized.sentences.into_iter().map(|sent| {
+                    Sentence::new(
+                        sent.into_tokens()
+                            .into_iter()
+                            .map(|token| {
+                                let space_after = token.space_after();
+                                let span = shift_span(token.span(), byte_start, char_start);
+                                Token::new(token.into_text(), span).with_space_after(space_after)
+                            })
+                            .collect(),
+                    )
+                }));
+            tokenized
+                .recoveries
+                .extend(
+                    line_tokenized
+                        .recoveries
+                        .into_iter()
+                        .map(|recovery| Recovery {
+                            offset: recovery.offset + char_start,
+                            ..recovery
+                        }),
+                );
+
+            // Skip the line and the separating space.
+            byte_start = line_end + 1;
+            char_start += line.chars().count() + 1;
+        }
+
+        Ok(tokenized)
+    }
+
+    fn tokenize_text(
+        &self,
+        tokenizer: &AlpinoTokenizer,
+        recover: bool,
+        text: &str,
+    ) -> Result<TokenizedParagraph, String> {
+        if recover {
+            let (sentences, recoveries) = tokenizer.tokenize_with_offsets_and_recovery(text);
+            Ok(TokenizedParagraph {
+                sentences,
+                recoveries,
+            })
+        } else {
+            let sentences = tokenizer
+                .tokenize_with_offsets(text)
+                .map_err(|err| format!("Cannot tokenize {}: {}", self.description(), err))?;
+            Ok(TokenizedParagraph {
+                sentences,
+                recoveries: Vec::new(),
+            })
+        }
+    }
+
+    /// Message for a recovery from rejected text in the paragraph.
+    pub fn recovery_message(&self, recovery: &Recovery) -> String {
+        let rejected = self
+            .text
+            .chars()
+            .skip(recovery.offset)
+            .take(recovery.len)
+            .collect::<String>();
+        format!(
+            "Recovered from rejected text {:?} at offset {} in {}",
+            rejected,
+            recovery.offset,
+            self.description()
+        )
+    }
+}
+
+/// Tokenized paragraph.
+#[derive(Default)]
+pub struct TokenizedParagraph {
+    /// Sentences with offsets in the paragraph text.
+    pub sentences: Vec<Sentence>,
+
+    /// Recoveries from rejected text.
+    pub recoveries: Vec<Recovery>,
+}
+
+fn shift_span(span: Span, byte_offset: usize, char_offset: usize) -> Span {
+    Span {
+        byte_start: span.byte_start + byte_offset,
+        byte_end: span.byte_end + byte_offset,
+        char_start: span.char_start + char_offset,
+        char_end: span.char_end + char_offset,
+    }
+}
+
+/// Iterator over the paragraphs of the input.
+///
+/// Paragraphs are separated by empty lines. In wikiextractor output,
+/// documents are delimited by `<doc>` tags and paragraph identifiers
+/// restart at every document.
+pub struct Paragraphs<R> {
+    lines: Lines<R>,
+    wikipedia: bool,
+    para: Vec<String>,
+    para_id: usize,
+    doc_id: Option<String>,
+    doc_title: Option<String>,
+    done: bool,
+}
+
+impl<R> Paragraphs<R>
+where
+    R: BufRead,
+{
+    pub fn new(read: R, wikipedia: bool) -> Self {
+        Paragraphs {
+            lines: read.lines(),
+            wikipedia,
+            para: Vec::new(),
+            para_id: 0,
+            doc_id: None,
+            doc_title: None,
+            done: false,
+        }
+    }
+
+    fn take_paragraph(&mut self) -> Option<Paragraph> {
+        if self.para.is_empty() {
+            return None;
+        }
+
+        let mut line_ends = Vec::with_capacity(self.para.len());
+        let mut line_end = 0;
+        for line in &self.para {
+            line_end += line.len();
+            line_ends.push(line_end);
+            // Account for the separating space.
+            line_end += 1;
+        }
+
+        let paragraph = Paragraph {
+            text: self.para.join(" "),
+            line_ends,
+            para_id: self.para_id,
+            doc_id: self.doc_id.clone(),
+            doc_title: self.doc_title.clone(),
+        };
+
+        self.para.clear();
+        self.para_id += 1;
+
+        Some(paragraph)
+    }
+}
+
+impl<R> Iterator for Paragraphs<R>
+where
+    R: BufRead,
+{
+    type Item = io::Result<Paragraph>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            let line = match self.lines.next() {
+                Some(Ok(line)) => line,
+                Some(Err(err)) => return Some(Err(err)),
+                None => {
+                    self.done = true;
+                    break;
+                }
+            };
+
+            if line.trim().is_empty() {
+                if let Some(paragraph) = self.take_paragraph() {
+                    return Some(Ok(paragraph));
+                }
+            } else if self.wikipedia {
+                if line.starts_with("<doc") {
+                    match WIKIPEDIA_DOC_EXPR.captures(&line) {
+                        Some(captures) => {
+                            self.doc_id = Some(captures.get(1).unwrap().as_str().to_owned());
+                            self.doc_title = Some(captures.get(2).unwrap().as_str().to_owned());
+                        }
+                        None => eprintln!("Could not read identifier in doc tag: {}", line),
+                    }
+
+                    self.para_id = 0;
+                } else if !line.starts_with("</doc") {
+                    self.para.push(line);
+                }
+            } else {
+                self.para.push(line);
+            }
+        }
+
+        self.take_paragraph().map(Ok)
+    }
+}
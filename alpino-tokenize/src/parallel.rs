@@ -0,0 +1,44 @@
+use std::io::BufRead;
+
+use alpino_tokenizer::map_parallel;
+use stdinout::OrExit;
+
+use crate::paragraphs::{Paragraph, Paragraphs};
+
+// Number of paragraphs that are read before they are tokenized. Within a
+// batch, the paragraphs are distributed dynamically over the threads.
+const BATCH_SIZE: usize = 4096;
+
+/// Tokenize paragraphs using `n_threads` threads.
+///
+/// The paragraphs are tokenized in batches with `tokenize`, using the
+/// threads of `map_parallel`. If `n_threads` is zero, the available
+/// parallelism is used. The results are passed to `write` in the order
+/// of the paragraphs.
+pub fn tokenize_paragraphs<R, T, F, W>(
+    paragraphs: Paragraphs<R>,
+    n_threads: usize,
+    tokenize: F,
+    mut write: W,
+) where
+    R: BufRead,
+    T: Send,
+    F: Fn(&Paragraph) -> T + Sync,
+    W: FnMut(T),
+{
+    let mut batch = Vec::with_capacity(BATCH_SIZE);
+    for paragraph in paragraphs {
+        batch.push(paragraph.or_exit("Cannot read line", 1));
+
+        if batch.len() == BATCH_SIZE {
+            map_parallel(&batch, n_threads, &tokenize)
+                .into_iter()
+                .for_each(&mut write);
+            batch.clear();
+        }
+    }
+
+    map_parallel(&batch, n_threads, &tokenize)
+        .into_iter()
+        .for_each(write);
+}
@@ -0,0 +1,172 @@
+use std::io::{BufWriter, Write};
+use std::process;
+
+use alpino_tokenizer::{AlpinoTokenizer, Tokenizer};
+use clap::{App, Arg, ArgMatches};
+use stdinout::{Input, OrExit, Output};
+
+use crate::paragraphs::{Paragraph, Paragraphs};
+use crate::parallel::tokenize_paragraphs;
+use crate::TokenizeApp;
+
+// Option constants
+static RECOVER: &str = "RECOVER";
+static THREADS: &str = "THREADS";
+static WIKIPEDIA: &str = "WIKIPEDIA";
+
+// Argument constants
+static INPUT: &str = "INPUT";
+static OUTPUT: &str = "OUTPUT";
+static PROTOBUF: &str = "PROTOBUF";
+
+pub struct SentencesApp {
+    input_filename: Option<String>,
+    output_filename: Option<String>,
+    protobuf_filename: String,
+    recover: bool,
+    threads: usize,
+    wikipedia: bool,
+}
+
+/// Sentence-split paragraph.
+struct SentencesParagraph {
+    /// Sentences, one per line.
+    text: String,
+
+    /// Messages about recoveries from rejected text.
+    recoveries: Vec<String>,
+}
+
+impl SentencesApp {
+    fn split_para(
+        &self,
+        tokenizer: &AlpinoTokenizer,
+        paragraph: &Paragraph,
+    ) -> Result<SentencesParagraph, String> {
+        let text = &paragraph.text;
+        if self.recover {
+            let (tokenized, recovered) = tokenizer.tokenize_with_offsets_and_recovery(text);
+            let sentences = tokenized
+                .iter()
+                .map(|sent| &text[sent.span().bytes()])
+                .collect::<Vec<_>>();
+
+            Ok(SentencesParagraph {
+                text: sentences.join("\n"),
+                recoveries: recovered
+                    .iter()
+                    .map(|recovery| paragraph.recovery_message(recovery))
+                    .collect(),
+            })
+        } else {
+            let sentences = tokenizer
+                .split_sentences(text)
+                .map_err(|err| format!("Cannot split {}: {}", paragraph.description(), err))?;
+
+            Ok(SentencesParagraph {
+                text: sentences.join("\n"),
+                recoveries: Vec::new(),
+            })
+        }
+    }
+}
+
+impl TokenizeApp for SentencesApp {
+    fn app() -> App<'static> {
+        App::new("sentences")
+            .about("Split input in sentences without tokenizing")
+            .arg(
+                Arg::with_name(PROTOBUF)
+                    .help("Tokenizer protobuf or compact transducer")
+                    .required(true)
+                    .index(1),
+            )
+            .arg(Arg::with_name(INPUT).help("Input corpus").index(2))
+            .arg(
+                Arg::with_name(OUTPUT)
+                    .help("Output with an untokenized sentence per line")
+                    .index(3),
+            )
+            .arg(
+                Arg::with_name(RECOVER)
+                    .long("recover")
+                    .help("Recover from text that cannot be processed"),
+            )
+            .arg(
+                Arg::with_name(THREADS)
+                    .long("threads")
+                    .takes_value(true)
+                    .default_value("1")
+                    .help("Number of tokenizer threads, 0 for the available parallelism"),
+            )
+            .arg(
+                Arg::with_name(WIKIPEDIA)
+                    .long("wikipedia")
+                    .help("Process wikiextractor output"),
+            )
+    }
+
+    fn parse(matches: &ArgMatches) -> Self {
+        let input_filename = matches.value_of(INPUT).map(ToOwned::to_owned);
+        let output_filename = matches.value_of(OUTPUT).map(ToOwned::to_owned);
+        let protobuf_filename = matches
+            .value_of(PROTOBUF)
+            .expect("Protobuf filename must be specified")
+            .to_owned();
+
+        let recover = matches.is_present(RECOVER);
+        let wikipedia = matches.is_present(WIKIPEDIA);
+        let threads = matches
+            .value_of(THREADS)
+            .unwrap()
+            .parse::<usize>()
+            .or_exit("Cannot parse number of threads", 1);
+
+        SentencesApp {
+            input_filename,
+            output_filename,
+            protobuf_filename,
+            recover,
+            threads,
+            wikipedia,
+        }
+    }
+
+    fn run(&self) {
+        let tokenizer =
+            AlpinoTokenizer::from_file(&self.protobuf_filename).or_exit("Cannot load tokenizer", 1);
+
+        let input = Input::from(self.input_filename.as_ref());
+        let reader = input.buf_read().or_exit("Cannot open input", 1);
+
+        let output = Output::from(self.output_filename.as_ref());
+        let mut writer = BufWriter::new(output.write().or_exit("Cannot open output", 1));
+
+        let mut first = true;
+        tokenize_paragraphs(
+            Paragraphs::new(reader, self.wikipedia),
+            self.threads,
+            |paragraph| self.split_para(&tokenizer, paragraph),
+            |paragraph| {
+                let paragraph = paragraph.unwrap_or_else(|err| {
+                    eprintln!("{}", err);
+                    process::exit(1)
+                });
+
+                for recovery in paragraph.recoveries {
+                    eprintln!("{}", recovery);
+                }
+
+                // Paragraphs are separated by an empty line.
+                if !first {
+                    writeln!(writer).or_exit("Cannot write paragraph", 1);
+                }
+                first = false;
+
+                writeln!(writer, "{}", paragraph.text).or_exit("Cannot write paragraph", 1);
+            },
+        );
+
+        writer.flush().or_exit("Cannot flush output", 1);
+    }
+}
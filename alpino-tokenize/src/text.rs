@@ -0,0 +1,190 @@
+use std::io::{BufWriter, Write};
+use std::process;
+
+use alpino_tokenizer::AlpinoTokenizer;
+use clap::{App, Arg, ArgMatches};
+use stdinout::{Input, OrExit, Output};
+
+use crate::paragraphs::{Paragraph, Paragraphs};
+use crate::parallel::tokenize_paragraphs;
+use crate::TokenizeApp;
+
+// Option constants
+static NO_SPLIT: &str = "NO_SPLIT";
+static RECOVER: &str = "RECOVER";
+static SENTENCE_PER_LINE: &str = "SENTENCE_PER_LINE";
+static THREADS: &str = "THREADS";
+static WIKIPEDIA: &str = "WIKIPEDIA";
+
+// Argument constants
+static INPUT: &str = "INPUT";
+static OUTPUT: &str = "OUTPUT";
+static PROTOBUF: &str = "PROTOBUF";
+
+pub struct TextApp {
+    input_filename: Option<String>,
+    output_filename: Option<String>,
+    protobuf_filename: String,
+    no_split: bool,
+    recover: bool,
+    sentence_per_line: bool,
+    threads: usize,
+    wikipedia: bool,
+}
+
+/// Tokenized paragraph.
+struct TokenizedParagraph {
+    /// Sentences, one per line, with space-separated tokens.
+    text: String,
+
+    /// Messages about recoveries from rejected text.
+    recoveries: Vec<String>,
+}
+
+impl TextApp {
+    fn tokenize_para(
+        &self,
+        tokenizer: &AlpinoTokenizer,
+        paragraph: &Paragraph,
+    ) -> Result<TokenizedParagraph, String> {
+        let tokenized = paragraph.tokenize(tokenizer, self.recover, self.sentence_per_line)?;
+
+        let mut text = String::with_capacity(paragraph.text.len());
+        for (idx, sent) in tokenized.sentences.iter().enumerate() {
+            if idx != 0 {
+                text.push('\n');
+            }
+
+            for (idx, token) in sent.tokens().iter().enumerate() {
+                if idx != 0 {
+                    text.push(' ');
+                }
+                text.push_str(token.text());
+            }
+        }
+
+        Ok(TokenizedParagraph {
+            text,
+            recoveries: tokenized
+                .recoveries
+                .iter()
+                .map(|recovery| paragraph.recovery_message(recovery))
+                .collect(),
+        })
+    }
+}
+
+impl TokenizeApp for TextApp {
+    fn app() -> App<'static> {
+        App::new("text")
+            .about("Tokenize input and output as plain text")
+            .arg(
+                Arg::with_name(PROTOBUF)
+                    .help("Tokenizer protobuf or compact transducer")
+                    .required(true)
+                    .index(1),
+            )
+            .arg(Arg::with_name(INPUT).help("Input corpus").index(2))
+            .arg(
+                Arg::with_name(OUTPUT)
+                    .help("Output with a sentence per line")
+                    .index(3),
+            )
+            .arg(
+                Arg::with_name(NO_SPLIT)
+                    .long("no-split")
+                    .help("Tokenize every paragraph as a single sentence"),
+            )
+            .arg(
+                Arg::with_name(RECOVER)
+                    .long("recover")
+                    .help("Recover from text that cannot be tokenized"),
+            )
+            .arg(
+                Arg::with_name(SENTENCE_PER_LINE)
+                    .long("sentence-per-line")
+                    .help("Tokenize every line as a single sentence"),
+            )
+            .arg(
+                Arg::with_name(THREADS)
+                    .long("threads")
+                    .takes_value(true)
+                    .default_value("1")
+                    .help("Number of tokenizer threads, 0 for the available parallelism"),
+            )
+            .arg(
+                Arg::with_name(WIKIPEDIA)
+                    .long("wikipedia")
+                    .help("Process wikiextractor output"),
+            )
+    }
+
+    fn parse(matches: &ArgMatches) -> Self {
+        let input_filename = matches.value_of(INPUT).map(ToOwned::to_owned);
+        let output_filename = matches.value_of(OUTPUT).map(ToOwned::to_owned);
+        let protobuf_filename = matches
+            .value_of(PROTOBUF)
+            .expect("Protobuf filename must be specified")
+            .to_owned();
+
+        let no_split = matches.is_present(NO_SPLIT);
+        let recover = matches.is_present(RECOVER);
+        let sentence_per_line = matches.is_present(SENTENCE_PER_LINE);
+        let wikipedia = matches.is_present(WIKIPEDIA);
+        let threads = matches
+            .value_of(THREADS)
+            .unwrap()
+            .parse::<usize>()
+            .or_exit("Cannot parse number of threads", 1);
+
+        TextApp {
+            input_filename,
+            output_filename,
+            protobuf_filename,
+            no_split,
+            recover,
+            sentence_per_line,
+            threads,
+            wikipedia,
+        }
+    }
+
+    fn run(&self) {
+        let tokenizer = AlpinoTokenizer::from_file(&self.protobuf_filename)
+            .or_exit("Cannot load tokenizer", 1)
+            .with_sentence_splitting(!(self.no_split || self.sentence_per_line));
+
+        let input = Input::from(self.input_filename.as_ref());
+        let reader = input.buf_read().or_exit("Cannot open input", 1);
+
+        let output = Output::from(self.output_filename.as_ref());
+        let mut writer = BufWriter::new(output.write().or_exit("Cannot open output", 1));
+
+        let mut first = true;
+        tokenize_paragraphs(
+            Paragraphs::new(reader, self.wikipedia),
+            self.threads,
+            |paragraph| self.tokenize_para(&tokenizer, paragraph),
+            |paragraph| {
+                let paragraph = paragraph.unwrap_or_else(|err| {
+                    eprintln!("{}", err);
+                    process::exit(1)
+                });
+
+                for recovery in paragraph.recoveries {
+                    eprintln!("{}", recovery);
+                }
+
+                // Paragraphs are separated by an empty line.
+                if !first {
+                    writeln!(writer).or_exit("Cannot write paragraph", 1);
+                }
+                first = false;
+
+                writeln!(writer, "{}", paragraph.text).or_exit("Cannot write paragraph", 1);
+            },
+        );
+
+        writer.flush().or_exit("Cannot flush output", 1);
+    }
+}
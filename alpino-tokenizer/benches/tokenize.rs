@@ -0,0 +1,137 @@
+use std::env;
+use std::fs::{self, File};
+use std::io::BufReader;
+
+use alpino_tokenizer::{
+    AddEnumerationMarkers, AlpinoTokenizer, FiniteStateTokenizer, TextProcessor, Tokenizer,
+};
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+
+// Set this environment variable to benchmark with the real Alpino
+// transducer, the toy transducer is used otherwise.
+static PROTOBUF_VAR: &str = "ALPINO_TOKENIZER_PROTOBUF";
+
+static PARAGRAPH: &str = "Groningen is een Hanzestad in het noorden van Nederland. \
+    De stad heeft ongeveer 230.000 inwoners en is daarmee de grootste stad van \
+    Noord-Nederland. Groningen heeft veel bezienswaardigheden, zoals de \
+    Martinitoren (97 meter), het Groninger Museum en de Noorderplantsoen! \
+    Wist je dat er 's zomers het festival 'Noorderzon' plaatsvindt? Ook zijn er \
+    1. de Grote Markt, 2. de Vismarkt en 3. het Prinsenhof.";
+
+fn protobuf_reader() -> BufReader<File> {
+    let path = env::var(PROTOBUF_VAR).unwrap_or_else(|_| "testdata/toy.proto".to_string());
+    BufReader::new(File::open(path).expect("Cannot open tokenizer protobuf"))
+}
+
+// Get the resident memory of the process in KiB. Only supported on Linux.
+fn resident_memory() -> Option<u64> {
+    fs::read_to_string("/proc/self/status")
+        .ok()?
+        .lines()
+        .find(|line| line.starts_with("VmRSS:"))?
+        .split_whitespace()
+        .nth(1)?
+        .parse()
+        .ok()
+}
+
+// Report the growth of the resident memory while loading a tokenizer.
+fn report_memory<T>(name: &str, load: impl FnOnce() -> T) -> T {
+    let before = resident_memory();
+    let tokenizer = load();
+    if let (Some(before), Some(after)) = (before, resident_memory()) {
+        eprintln!(
+            "{}: resident memory grew by {} KiB while loading",
+            name,
+            after.saturating_sub(before)
+        );
+    }
+    tokenizer
+}
+
+fn tokenize(c: &mut Criterion) {
+    let text = PARAGRAPH.repeat(10);
+
+    let mut group = c.benchmark_group("tokenize");
+    group.throughput(Throughput::Bytes(text.len() as u64));
+
+    let fst = report_memory("finite_state", || {
+        FiniteStateTokenizer::from_buf_read(protobuf_reader()).unwrap()
+    });
+    group.bench_function("finite_state", |b| b.iter(|| fst.tokenize(&text).unwrap()));
+    group.bench_function("finite_state_text", |b| {
+        b.iter(|| fst.tokenize_text(&text).unwrap())
+    });
+
+    let mut compact_data = Vec::new();
+    fst.write_compact(&mut compact_data).unwrap();
+    let compact = report_memory("finite_state_compact", || {
+        FiniteStateTokenizer::from_compact(compact_data).unwrap()
+    });
+    group.bench_function("finite_state_compact", |b| {
+        b.iter(|| compact.tokenize(&text).unwrap())
+    });
+
+    let alpino = AlpinoTokenizer::from_buf_read(protobuf_reader()).unwrap();
+    group.bench_function("alpino", |b| b.iter(|| alpino.tokenize(&text).unwrap()));
+
+    let mut session = alpino.session();
+    group.bench_function("alpino_session", |b| {
+        b.iter(|| session.tokenize_text(&text).map(|t| t.n_tokens()).unwrap())
+    });
+
+    group.finish();
+}
+
+// Compare the transition index with the linear scan over the transitions
+// of a state that it replaced.
+fn transition_lookup(c: &mut Criterion) {
+    let text = PARAGRAPH.repeat(10);
+
+    let mut group = c.benchmark_group("transition_lookup");
+    group.throughput(Throughput::Bytes(text.len() as u64));
+
+    let fst = FiniteStateTokenizer::from_buf_read(protobuf_reader()).unwrap();
+    group.bench_function("linear_scan", |b| {
+        b.iter(|| fst.tokenize_linear_scan(&text).unwrap())
+    });
+    group.bench_function("index", |b| b.iter(|| fst.tokenize_text(&text).unwrap()));
+
+    let mut compact_data = Vec::new();
+    fst.write_compact(&mut compact_data).unwrap();
+    let compact = FiniteStateTokenizer::from_compact(compact_data).unwrap();
+    group.bench_function("compact_linear_scan", |b| {
+        b.iter(|| compact.tokenize_linear_scan(&text).unwrap())
+    });
+    group.bench_function("compact_index", |b| {
+        b.iter(|| compact.tokenize_text(&text).unwrap())
+    });
+
+    group.finish();
+}
+
+fn enumerations(c: &mut Criterion) {
+    // Long enumerations, such as the articles of a law.
+    let text = (1..=200)
+        .map(|n| format!("{}. Dit is artikel {}", n, n))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let mut group = c.benchmark_group("enumerations");
+    group.throughput(Throughput::Bytes(text.len() as u64));
+
+    let mut marked = String::new();
+    group.bench_function("add_enumeration_markers", |b| {
+        b.iter(|| AddEnumerationMarkers.process(&text, &mut marked))
+    });
+
+    let paragraph = PARAGRAPH.repeat(10);
+    group.bench_function("add_enumeration_markers_paragraph", |b| {
+        b.iter(|| AddEnumerationMarkers.process(&paragraph, &mut marked))
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, tokenize, transition_lookup, enumerations);
+criterion_main!(benches);
@@ -0,0 +1,441 @@
+use std::ops::Range;
+
+// Maximum number of cells of the table that is used to compute the
+// differences between two strings. Longer strings are aligned as a
+// single edit.
+const MAX_DIFF_CELLS: usize = 1 << 16;
+
+/// Edit that replaces a part of a text.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Edit {
+    /// Byte range of the replaced part of the original text.
+    pub source: Range<usize>,
+
+    /// Byte range of the replacement in the edited text.
+    pub target: Range<usize>,
+}
+
+/// Alignment of an edited text with the original text.
+///
+/// The alignment is stored as a list of non-overlapping edits, sorted by
+/// their position in the text. Text outside the edits is unchanged. The
+/// alignment is used to project offsets in the edited text back to the
+/// original text.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct EditAlignment {
+    edits: Vec<Edit>,
+}
+
+impl EditAlignment {
+    /// Construct an alignment of a text that was not edited.
+    pub fn new() -> Self {
+        EditAlignment::default()
+    }
+
+    /// Remove all edits.
+    pub fn clear(&mut self) {
+        self.edits.clear();
+    }
+
+    /// Get the edits.
+    pub fn edits(&self) -> &[Edit] {
+        &self.edits
+    }
+
+    /// Add an edit.
+    ///
+    /// Edits must be added in text order. Edits that are adjacent to the
+    /// previous edit are merged with it.
+    ///
+    /// Panics when the edit overlaps with or precedes the previous edit.
+    pub fn push(&mut self, source: Range<usize>, target: Range<usize>) {
+        if source.is_empty() && target.is_empty() {
+            return;
+        }
+
+        if let Some(last) = self.edits.last_mut() {
+            assert!(
+                source.start >= last.source.end && target.start >= last.target.end,
+                "Edits must be added in text order"
+            );
+
+            if source.start == last.source.end && target.start == last.target.end {
+                last.source.end = source.end;
+                last.target.end = target.end;
+                return;
+            }
+        }
+
+        self.edits.push(Edit { source, target });
+    }
+
+    /// Add the edits that rewrite `source` to `target`.
+    ///
+    /// `source_offset` and `target_offset` are the byte offsets of
+    /// `source` and `target` in the original and edited text. The edits
+    /// are computed from the character-level differences between the
+    /// strings. Strings that are too long to compare efficiently are
+    /// aligned as a single edit, after stripping their common prefix and
+    /// suffix.
+    pub fn push_diff(
+        &mut self,
+        source: &str,
+        source_offset: usize,
+        target: &str,
+        target_offset: usize,
+    ) {
+        let prefix_len = source
+            .char_indices()
+            .zip(target.chars())
+            .find(|&((_, source_ch), target_ch)| source_ch != target_ch)
+            .map(|((idx, _), _)| idx)
+            .unwrap_or_else(|| source.len().min(target.len()));
+        let (source, target) = (&source[prefix_len..], &target[prefix_len..]);
+
+        let suffix_len = source
+            .char_indices()
+            .rev()
+            .zip(target.chars().rev())
+            .find(|&((_, source_ch), target_ch)| source_ch != target_ch)
+            .map(|((idx, ch), _)| source.len() - idx - ch.len_utf8())
+            .unwrap_or_else(|| source.len().min(target.len()));
+        let source = &source[..source.len() - suffix_len];
+        let target = &target[..target.len() - suffix_len];
+
+        let source_offset = source_offset + prefix_len;
+        let target_offset = target_offset + prefix_len;
+
+        let source_chars = char_offsets(source);
+        let target_chars = char_offsets(target);
+        let (n_source, n_target) = (source_chars.len() - 1, target_chars.len() - 1);
+
+        if n_source == n_target {
+            // Align the characters by their position.
+            for idx in 0..n_source {
+                let source_range = source_chars[idx]..source_chars[idx + 1];
+                let target_range = target_chars[idx]..target_chars[idx + 1];
+                if source[source_range.clone()] != target[target_range.clone()] {
+                    self.push(
+                        offset_range(source_range, source_offset),
+                        offset_range(target_range, target_offset),
+                    );
+                }
+            }
+        } else if (n_source + 1) * (n_target + 1) <= MAX_DIFF_CELLS {
+            self.push_lcs_diff(
+                source,
+                &source_chars,
+                source_offset,
+                target,
+                &target_chars,
+                target_offset,
+            );
+        } else {
+            self.push(
+                source_offset..source_offset + source.len(),
+                target_offset..target_offset + target.len(),
+            );
+        }
+    }
+
+    // Add the edits of the longest common subsequence of the characters.
+    fn push_lcs_diff(
+        &mut self,
+        source: &str,
+        source_chars: &[usize],
+        source_offset: usize,
+        target: &str,
+        target_chars: &[usize],
+        target_offset: usize,
+    ) {
+        let (n_source, n_target) = (source_chars.len() - 1, target_chars.len() - 1);
+        let source_char = |idx: usize| &source[source_chars[idx]..source_chars[idx + 1]];
+        let target_char = |idx: usize| &target[target_chars[idx]..target_chars[idx + 1]];
+
+        // lcs[i * (n_target + 1) + j] is the length of the longest common
+        // subsequence of the source suffix i and the target suffix j.
+        let width = n_target + 1;
+        let mut lcs = vec![0u32; (n_source + 1) * width];
+        for i in (0..n_source).rev() {
+            for j in (0..n_target).rev() {
+                lcs[i * width + j] = if source_char(i) == target_char(j) {
+                    lcs[(i + 1) * width + j + 1] + 1
+                } else {
+                    lcs[(i + 1) * width + j].max(lcs[i * width + j + 1])
+                };
+            }
+        }
+
+        let (mut i, mut j) = (0, 0);
+        while i < n_source || j < n_target {
+            if i < n_source && j < n_target && source_char(i) == target_char(j) {
+                i += 1;
+                j += 1;
+            } else if j == n_target
+                || (i < n_source && lcs[(i + 1) * width + j] >= lcs[i * width + j + 1])
+            {
+                self.push(
+                    offset_range(source_chars[i]..source_chars[i + 1], source_offset),
+                    offset_range(target_chars[j]..target_chars[j], target_offset),
+                );
+                i += 1;
+            } else {
+                self.push(
+                    offset_range(source_chars[i]..source_chars[i], source_offset),
+                    offset_range(target_chars[j]..target_chars[j + 1], target_offset),
+                );
+                j += 1;
+            }
+        }
+    }
+
+    /// Project a byte range of the edited text onto the original text.
+    ///
+    /// A range that starts or ends within an edit is extended to cover
+    /// the replaced part of the original text. An empty range is projected
+    /// onto an empty range.
+    pub fn project(&self, target: Range<usize>) -> Range<usize> {
+        project(
+            &self.edits,
+            target,
+            |edit| &edit.target,
+            |edit| &edit.source,
+        )
+    }
+
+    /// Project a byte range of the original text onto the edited text.
+    pub fn project_forward(&self, source: Range<usize>) -> Range<usize> {
+        project(
+            &self.edits,
+            source,
+            |edit| &edit.source,
+            |edit| &edit.target,
+        )
+    }
+
+    /// Compose this alignment with the alignment of a subsequent edit.
+    ///
+    /// If this alignment aligns a text `b` with an original text `a` and
+    /// `next` aligns a text `c` with `b`, the composed alignment aligns `c`
+    /// with `a`.
+    pub fn compose(&self, next: &EditAlignment) -> EditAlignment {
+        if self.edits.is_empty() {
+            return next.clone();
+        } else if next.edits.is_empty() {
+            return self.clone();
+        }
+
+        let mut edits = self
+            .edits
+            .iter()
+            .map(|edit| Edit {
+                source: edit.source.clone(),
+                target: next.project_forward(edit.target.clone()),
+            })
+            .chain(next.edits.iter().map(|edit| Edit {
+                source: self.project(edit.source.clone()),
+                target: edit.target.clone(),
+            }))
+            .collect::<Vec<_>>();
+        edits.sort_by_key(|edit| (edit.source.start, edit.target.start));
+
+        // Merge overlapping edits. Edits that only touch are kept apart,
+        // so that e.g. a rewritten space does not widen an adjacent edit.
+        let mut composed: Vec<Edit> = Vec::with_capacity(edits.len());
+        for edit in edits {
+            match composed.last_mut() {
+                Some(last)
+                    if edit.source.start < last.source.end
+                        || edit.target.start < last.target.end =>
+                {
+                    last.source.end = last.source.end.max(edit.source.end);
+                    last.target.end = last.target.end.max(edit.target.end);
+                }
+                _ => composed.push(edit),
+            }
+        }
+
+        EditAlignment { edits: composed }
+    }
+}
+
+/// Align tokenizer output with the text that was tokenized.
+///
+/// The tokenizers only insert or remove whitespace and remove some
+/// invisible characters (such as zero-width spaces). So, the characters
+/// of the output can be matched one-by-one against the input, skipping
+/// over whitespace and invisible characters in the input. If a character
+/// does not match the input, it is assumed to be a rewrite of the input
+/// character.
+pub(crate) fn output_alignment(text: &str, output: &str) -> EditAlignment {
+    let mut alignment = EditAlignment::new();
+    let mut source = text.char_indices().peekable();
+    let mut target = output.char_indices().peekable();
+
+    loop {
+        let (source_range, target_range) = match (source.peek(), target.peek()) {
+            (None, None) => break,
+            (Some(&(source_offset, _)), None) => {
+                alignment.push(source_offset..text.len(), output.len()..output.len());
+                break;
+            }
+            (None, Some(&(target_offset, target_ch))) => {
+                target.next();
+                alignment.push(
+                    text.len()..text.len(),
+                    target_offset..target_offset + target_ch.len_utf8(),
+                );
+                continue;
+            }
+            (Some(&(source_offset, source_ch)), Some(&(target_offset, target_ch))) => {
+                if source_ch == target_ch {
+                    source.next();
+                    target.next();
+                    continue;
+                }
+
+                let source_end = source_offset + source_ch.len_utf8();
+                let target_end = target_offset + target_ch.len_utf8();
+                if is_skippable(source_ch) && !target_ch.is_whitespace() {
+                    source.next();
+                    (source_offset..source_end, target_offset..target_offset)
+                } else if target_ch.is_whitespace() && !source_ch.is_whitespace() {
+                    target.next();
+                    (source_offset..source_offset, target_offset..target_end)
+                } else {
+                    source.next();
+                    target.next();
+                    (source_offset..source_end, target_offset..target_end)
+                }
+            }
+        };
+
+        alignment.push(source_range, target_range);
+    }
+
+    alignment
+}
+
+pub(crate) fn is_skippable(ch: char) -> bool {
+    ch.is_whitespace() || matches!(ch, '\u{200b}'..='\u{200d}' | '\u{2060}' | '\u{feff}')
+}
+
+// Byte offsets of the characters of `text`, followed by the length of `text`.
+fn char_offsets(text: &str) -> Vec<usize> {
+    text.char_indices()
+        .map(|(idx, _)| idx)
+        .chain(Some(text.len()))
+        .collect()
+}
+
+fn offset_range(range: Range<usize>, offset: usize) -> Range<usize> {
+    range.start + offset..range.end + offset
+}
+
+fn project(
+    edits: &[Edit],
+    range: Range<usize>,
+    from: impl Fn(&Edit) -> &Range<usize>,
+    to: impl Fn(&Edit) -> &Range<usize>,
+) -> Range<usize> {
+    let end = project_end(edits, range.end, &from, &to);
+    if range.is_empty() {
+        return end..end;
+    }
+
+    // The start is projected after edits that start at the offset.
+    let idx = edits.partition_point(|edit| from(edit).start <= range.start);
+    let start = match idx.checked_sub(1).map(|idx| &edits[idx]) {
+        Some(edit) if range.start < from(edit).end => to(edit).start,
+        Some(edit) => to(edit).end + (range.start - from(edit).end),
+        None => range.start,
+    };
+
+    start..end.max(start)
+}
+
+fn project_end(
+    edits: &[Edit],
+    offset: usize,
+    from: impl Fn(&Edit) -> &Range<usize>,
+    to: impl Fn(&Edit) -> &Range<usize>,
+) -> usize {
+    // The end is projected before edits that start at the offset.
+    let idx = edits.partition_point(|edit| from(edit).start < offset);
+    match idx.checked_sub(1).map(|idx| &edits[idx]) {
+        Some(edit) if offset <= from(edit).end => to(edit).end,
+        Some(edit) => to(edit).end + (offset - from(edit).end),
+        None => offset,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{output_alignment, Edit, EditAlignment};
+
+    fn diff(source: &str, target: &str) -> EditAlignment {
+        let mut alignment = EditAlignment::new();
+        alignment.push_diff(source, 0, target, 0);
+        alignment
+    }
+
+    fn edit(source: std::ops::Range<usize>, target: std::ops::Range<usize>) -> Edit {
+        Edit { source, target }
+    }
+
+    #[test]
+    fn diff_edits() {
+        assert_eq!(diff("abc", "abc").edits(), &[]);
+        assert_eq!(diff("1. foo", "1# foo").edits(), &[edit(1..2, 1..2)]);
+        assert_eq!(
+            diff(" -zoals gezegd- ", " - zoals gezegd - ").edits(),
+            &[edit(2..2, 2..3), edit(14..14, 15..16)]
+        );
+        assert_eq!(
+            diff("€ 10", "10 euro").edits(),
+            &[edit(0..4, 0..0), edit(6..6, 2..7)]
+        );
+        assert_eq!(diff("( buiten)", "(buiten)").edits(), &[edit(1..2, 1..1)]);
+    }
+
+    #[test]
+    fn project_offsets() {
+        let alignment = diff("€ 10 kost", "10 euro kost");
+        assert_eq!(alignment.project(0..2), 4..6);
+        assert_eq!(alignment.project(3..7), 6..6);
+        assert_eq!(alignment.project(8..12), 7..11);
+        assert_eq!(alignment.project_forward(4..6), 0..2);
+        assert_eq!(alignment.project_forward(7..11), 8..12);
+
+        let alignment = diff("  ", "");
+        assert_eq!(alignment.project(0..0), 0..0);
+    }
+
+    #[test]
+    fn compose_alignments() {
+        let first = diff("1. a -b- c", "1# a -b- c");
+        let second = diff("1# a -b- c", "1# a - b - c");
+        let third = diff("1# a - b - c", "1. a - b - c");
+
+        let composed = first.compose(&second).compose(&third);
+        assert_eq!(composed.project(0..2), 0..2);
+        assert_eq!(composed.project(5..6), 5..6);
+        assert_eq!(composed.project(7..8), 6..7);
+        assert_eq!(composed.project(9..10), 7..8);
+        assert_eq!(composed.project(11..12), 9..10);
+
+        assert_eq!(EditAlignment::new().compose(&second), second);
+        assert_eq!(second.compose(&EditAlignment::new()), second);
+    }
+
+    #[test]
+    fn align_output() {
+        let text = "Hallo,\u{200b}wereld.";
+        let output = "Hallo , wereld .";
+        let alignment = output_alignment(text, output);
+        assert_eq!(alignment.project(0..5), 0..5);
+        assert_eq!(alignment.project(6..7), 5..6);
+        assert_eq!(alignment.project(8..14), 9..15);
+        assert_eq!(alignment.project(15..16), 15..16);
+    }
+}
@@ -1,50 +1,473 @@
 use std::io::BufRead;
+use std::ops::Range;
+use std::path::Path;
 
-use crate::postproc::postprocess;
-use crate::preproc::preprocess;
+use static_assertions::assert_impl_all;
 
+use crate::alignment::{output_alignment, Edit, EditAlignment};
+use crate::offsets::{align_with, Sentence};
+use crate::pipeline::Pipeline;
+use crate::postproc::join_sentences;
+
+use crate::tokenized::TokenizedText;
 use crate::tokenizer::Tokenizer;
 use crate::util::str_to_tokens;
-use crate::{FiniteStateTokenizer, TokenizerError};
+use crate::{FiniteStateTokenizer, Recovery, TokenizerError};
 
 /// Alpino tokenizer and sentence splitter.
+///
+/// This tokenizer applies a preprocessing pipeline to the text, tokenizes
+/// the text with the transducer, and then applies a postprocessing pipeline
+/// to the tokenizer output. Since the pipelines operate on a complete
+/// paragraph, this tokenizer does not support streaming. The streaming
+/// methods of the underlying `FiniteStateTokenizer` (`tokenize_chars` and
+/// `tokenize_buf_read`) do not apply the pipelines.
 pub struct AlpinoTokenizer {
     inner: FiniteStateTokenizer,
+    preprocessing: Pipeline,
+    postprocessing: Pipeline,
+    sentence_splitting: bool,
 }
 
+// The tokenizer is shared between threads by `tokenize_batch`.
+assert_impl_all!(AlpinoTokenizer: Send, Sync);
+
+/// Placeholder for `#` characters of the input during postprocessing.
+///
+/// `RemoveEnumerationMarkers` cannot distinguish the `#` markers that
+/// are inserted by preprocessing from `#` characters in the input, such
+/// as in `C#`. So, the latter are replaced by this noncharacter while
+/// the postprocessing pipeline is applied.
+const INPUT_HASH: char = '\u{fdd0}';
+
 impl AlpinoTokenizer {
+    /// Construct an Alpino tokenizer from a finite state tokenizer.
+    pub fn new(inner: FiniteStateTokenizer) -> Self {
+        AlpinoTokenizer {
+            inner,
+            preprocessing: Pipeline::alpino_preprocessing(),
+            postprocessing: Pipeline::alpino_postprocessing(),
+            sentence_splitting: true,
+        }
+    }
+
     pub fn from_buf_read<R>(read: R) -> Result<Self, TokenizerError>
     where
         R: BufRead,
     {
-        Ok(AlpinoTokenizer {
-            inner: FiniteStateTokenizer::from_buf_read(read)?,
-        })
+        Ok(AlpinoTokenizer::new(FiniteStateTokenizer::from_buf_read(
+            read,
+        )?))
+    }
+
+    /// Load the tokenizer transducer from a file.
+    ///
+    /// See `FiniteStateTokenizer::from_file` for the supported formats.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, TokenizerError> {
+        Ok(AlpinoTokenizer::new(FiniteStateTokenizer::from_file(path)?))
+    }
+
+    /// Get the preprocessing pipeline.
+    pub fn preprocessing(&self) -> &Pipeline {
+        &self.preprocessing
+    }
+
+    /// Get the preprocessing pipeline mutably.
+    ///
+    /// The preprocessing pipeline is applied to the text before it is
+    /// tokenized by the transducer. The default pipeline is
+    /// `Pipeline::alpino_preprocessing`. Offsets are mapped back to the
+    /// input text through the edits that the preprocessors record. This
+    /// also applies to the offsets of `TokenizerError::NoTransition`
+    /// errors, which refer to the input text rather than the preprocessed
+    /// text.
+    pub fn preprocessing_mut(&mut self) -> &mut Pipeline {
+        &mut self.preprocessing
+    }
+
+    /// Get the postprocessing pipeline.
+    pub fn postprocessing(&self) -> &Pipeline {
+        &self.postprocessing
+    }
+
+    /// Get the postprocessing pipeline mutably.
+    ///
+    /// The postprocessing pipeline is applied to the output of the
+    /// transducer. The default pipeline is `Pipeline::alpino_postprocessing`.
+    pub fn postprocessing_mut(&mut self) -> &mut Pipeline {
+        &mut self.postprocessing
+    }
+
+    /// Enable or disable sentence splitting.
+    ///
+    /// When sentence splitting is disabled, the sentences that are found
+    /// by the transducer are joined, so that every paragraph is tokenized
+    /// as a single sentence. This is useful for input that is already
+    /// split in sentences. Sentence splitting is enabled by default.
+    pub fn with_sentence_splitting(mut self, sentence_splitting: bool) -> Self {
+        self.sentence_splitting = sentence_splitting;
+        self
+    }
+
+    /// Check whether sentence splitting is enabled.
+    pub fn sentence_splitting(&self) -> bool {
+        self.sentence_splitting
+    }
+
+    pub(crate) fn inner(&self) -> &FiniteStateTokenizer {
+        &self.inner
+    }
+
+    /// Sentence-split and tokenize paragraphs in parallel.
+    ///
+    /// See `Tokenizer::tokenize_batch`.
+    pub fn tokenize_batch<S>(
+        &self,
+        paragraphs: &[S],
+        n_threads: usize,
+    ) -> Vec<Result<TokenizedText, TokenizerError>>
+    where
+        S: AsRef<str> + Sync,
+    {
+        Tokenizer::tokenize_batch(self, paragraphs, n_threads)
+    }
+
+    /// Sentence-split and tokenize a paragraph, recovering from errors.
+    ///
+    /// See `FiniteStateTokenizer::tokenize_with_recovery` for a description
+    /// of the recovery strategy. The offsets of the recoveries are character
+    /// offsets in `text`.
+    pub fn tokenize_with_recovery(&self, text: &str) -> (Vec<Vec<String>>, Vec<Recovery>) {
+        let (preprocessed, alignment) = self.preprocessing.process_aligned(text);
+        let (raw, recoveries) = self.inner.tokenize_raw_with_recovery(preprocessed.chars());
+        let input_hashes = input_hashes(
+            &preprocessed,
+            &alignment,
+            &output_alignment(&preprocessed, &raw),
+            &raw,
+        );
+        let mut tokenized = String::new();
+        self.postprocess_into(&raw, &input_hashes, &mut String::new(), &mut tokenized);
+        let recoveries = recoveries
+            .into_iter()
+            .map(|recovery| project_recovery(text, &preprocessed, &alignment, recovery))
+            .collect();
+        (str_to_tokens(&tokenized), recoveries)
+    }
+
+    /// Sentence-split and tokenize a paragraph with offsets, recovering from
+    /// errors.
+    ///
+    /// This method combines `tokenize_with_recovery` and
+    /// `Tokenizer::tokenize_with_offsets`.
+    pub fn tokenize_with_offsets_and_recovery(&self, text: &str) -> (Vec<Sentence>, Vec<Recovery>) {
+        let (preprocessed, alignment) = self.preprocessing.process_aligned(text);
+        let (raw, recoveries) = self.inner.tokenize_raw_with_recovery(preprocessed.chars());
+        let (tokenized, output_alignment) =
+            self.postprocess_aligned(&preprocessed, &alignment, &raw);
+        let recoveries = recoveries
+            .into_iter()
+            .map(|recovery| project_recovery(text, &preprocessed, &alignment, recovery))
+            .collect();
+        (
+            align_with(text, &tokenized, &alignment.compose(&output_alignment)),
+            recoveries,
+        )
+    }
+
+    /// Project the offset of a transducer error onto `text`.
+    ///
+    /// The transducer reports offsets in the preprocessed text. The
+    /// preprocessing pipeline is applied again to align it with `text`.
+    pub(crate) fn project_error(&self, text: &str, err: TokenizerError) -> TokenizerError {
+        let (preprocessed, alignment) = self.preprocessing.process_aligned(text);
+        project_error(text, &preprocessed, &alignment, err)
+    }
+
+    /// Find the `#` characters of `text` in the transducer output `raw`.
+    ///
+    /// `preprocessed` is the result of applying the preprocessing
+    /// pipeline to `text`. Returns the byte offsets of the characters in
+    /// `raw`.
+    pub(crate) fn input_hashes(&self, text: &str, preprocessed: &str, raw: &str) -> Vec<usize> {
+        // Only compute the alignments when the input has `#` characters.
+        if !text.contains('#') {
+            return Vec::new();
+        }
+
+        let (_, alignment) = self.preprocessing.process_aligned(text);
+        input_hashes(
+            preprocessed,
+            &alignment,
+            &output_alignment(preprocessed, raw),
+            raw,
+        )
+    }
+
+    /// Postprocess the transducer output `raw`, writing the result to
+    /// `output`.
+    ///
+    /// `input_hashes` are the offsets of the `#` characters of the input
+    /// in `raw`, which are protected from the postprocessing pipeline.
+    pub(crate) fn postprocess_into(
+        &self,
+        raw: &str,
+        input_hashes: &[usize],
+        scratch: &mut String,
+        output: &mut String,
+    ) {
+        if input_hashes.is_empty() {
+            self.postprocessing.process_into(raw, scratch, output);
+        } else {
+            let protected = replace_chars(raw, input_hashes.iter().copied(), INPUT_HASH, None);
+            self.postprocessing
+                .process_into(&protected, scratch, output);
+            restore_input_hashes(output, None);
+        }
+
+        if !self.sentence_splitting {
+            join_sentences(output);
+        }
+    }
+
+    /// Postprocess the transducer output `raw` of `preprocessed`.
+    ///
+    /// `alignment` is the alignment of `preprocessed` with the input.
+    /// Returns the tokenized text and its alignment with `preprocessed`.
+    fn postprocess_aligned(
+        &self,
+        preprocessed: &str,
+        alignment: &EditAlignment,
+        raw: &str,
+    ) -> (TokenizedText, EditAlignment) {
+        let mut raw_alignment = output_alignment(preprocessed, raw);
+        let input_hashes = input_hashes(preprocessed, alignment, &raw_alignment, raw);
+
+        let (mut tokenized, alignment) = if input_hashes.is_empty() {
+            self.postprocessing.process_aligned(raw)
+        } else {
+            let mut protect_alignment = EditAlignment::new();
+            let protected =
+                replace_chars(raw, input_hashes, INPUT_HASH, Some(&mut protect_alignment));
+            raw_alignment = raw_alignment.compose(&protect_alignment);
+
+            let (mut tokenized, alignment) = self.postprocessing.process_aligned(&protected);
+            let mut restore_alignment = EditAlignment::new();
+            restore_input_hashes(&mut tokenized, Some(&mut restore_alignment));
+            (tokenized, alignment.compose(&restore_alignment))
+        };
+
+        // Joining sentences replaces newlines by spaces, which does not
+        // change the offsets.
+        if !self.sentence_splitting {
+            join_sentences(&mut tokenized);
+        }
+
+        (
+            TokenizedText::new(tokenized),
+            raw_alignment.compose(&alignment),
+        )
     }
 }
 
 impl Tokenizer for AlpinoTokenizer {
-    fn tokenize(&self, text: &str) -> Option<Vec<Vec<String>>> {
-        let tokenized = preprocess(text);
-        let tokenized = self.inner.tokenize_raw(tokenized.chars())?;
-        let tokenized = postprocess(&tokenized);
-        Some(str_to_tokens(&tokenized))
+    fn tokenize_text(&self, text: &str) -> Result<TokenizedText, TokenizerError> {
+        let preprocessed = self.preprocessing.process(text);
+        let raw = self
+            .inner
+            .tokenize_raw(preprocessed.chars())
+            .map_err(|err| self.project_error(text, err))?;
+        let input_hashes = self.input_hashes(text, &preprocessed, &raw);
+        let mut tokenized = String::new();
+        self.postprocess_into(&raw, &input_hashes, &mut String::new(), &mut tokenized);
+        Ok(TokenizedText::new(tokenized))
+    }
+
+    /// Sentence-split and tokenize a paragraph of text with offsets.
+    ///
+    /// The offsets are projected through the edits of the preprocessing
+    /// pipeline, the transducer, and the postprocessing pipeline.
+    fn tokenize_with_offsets(&self, text: &str) -> Result<Vec<Sentence>, TokenizerError> {
+        let (preprocessed, alignment) = self.preprocessing.process_aligned(text);
+        let raw = self
+            .inner
+            .tokenize_raw(preprocessed.chars())
+            .map_err(|err| project_error(text, &preprocessed, &alignment, err))?;
+        let (tokenized, output_alignment) =
+            self.postprocess_aligned(&preprocessed, &alignment, &raw);
+        Ok(align_with(
+            text,
+            &tokenized,
+            &alignment.compose(&output_alignment),
+        ))
+    }
+}
+
+/// Find the `#` characters of the input in the transducer output `raw`.
+///
+/// `alignment` aligns `preprocessed` with the input and `raw_alignment`
+/// aligns `raw` with `preprocessed`. A `#` character is taken from the
+/// input when it is not part of an edit of the preprocessing pipeline.
+/// Returns the byte offsets of the characters in `raw`.
+fn input_hashes(
+    preprocessed: &str,
+    alignment: &EditAlignment,
+    raw_alignment: &EditAlignment,
+    raw: &str,
+) -> Vec<usize> {
+    // The placeholder cannot be distinguished from noncharacters in the
+    // input, fall back to postprocessing without placeholders.
+    if raw.contains(INPUT_HASH) {
+        return Vec::new();
+    }
+
+    let edits = alignment.edits();
+    preprocessed
+        .match_indices('#')
+        .map(|(offset, _)| offset)
+        .filter(|&offset| !is_edited(edits, offset))
+        .map(|offset| raw_alignment.project_forward(offset..offset + 1))
+        .filter(|range| &raw[range.clone()] == "#")
+        .map(|range| range.start)
+        .collect()
+}
+
+/// Check whether the byte at `offset` is part of an edit's target.
+fn is_edited(edits: &[Edit], offset: usize) -> bool {
+    let idx = edits.partition_point(|edit| edit.target.end <= offset);
+    edits
+        .get(idx)
+        .map(|edit| edit.target.contains(&offset))
+        .unwrap_or(false)
+}
+
+/// Replace the characters at the byte offsets `offsets` by `replacement`.
+///
+/// The offsets must be sorted. The edits are added to `alignment` when
+/// it is provided.
+fn replace_chars(
+    text: &str,
+    offsets: impl IntoIterator<Item = usize>,
+    replacement: char,
+    mut alignment: Option<&mut EditAlignment>,
+) -> String {
+    let mut replaced = String::with_capacity(text.len());
+    let mut last_end = 0;
+    for offset in offsets {
+        let ch_len = text[offset..]
+            .chars()
+            .next()
+            .map(char::len_utf8)
+            .unwrap_or(0);
+        replaced.push_str(&text[last_end..offset]);
+        if let Some(alignment) = alignment.as_deref_mut() {
+            alignment.push(
+                offset..offset + ch_len,
+                replaced.len()..replaced.len() + replacement.len_utf8(),
+            );
+        }
+        replaced.push(replacement);
+        last_end = offset + ch_len;
+    }
+    replaced.push_str(&text[last_end..]);
+    replaced
+}
+
+/// Replace the placeholders of `#` characters by `#` again.
+fn restore_input_hashes(text: &mut String, alignment: Option<&mut EditAlignment>) {
+    let offsets = text
+        .match_indices(INPUT_HASH)
+        .map(|(offset, _)| offset)
+        .collect::<Vec<_>>();
+    *text = replace_chars(text, offsets, '#', alignment);
+}
+
+/// Project a recovery in the preprocessed text onto the original text.
+fn project_recovery(
+    text: &str,
+    preprocessed: &str,
+    alignment: &EditAlignment,
+    recovery: Recovery,
+) -> Recovery {
+    let chars = project_chars(
+        text,
+        preprocessed,
+        alignment,
+        recovery.offset..recovery.offset + recovery.len,
+    );
+    Recovery {
+        offset: chars.start,
+        len: chars.len(),
+        ..recovery
+    }
+}
+
+/// Project the offset of a transducer error in the preprocessed text onto
+/// the original text.
+fn project_error(
+    text: &str,
+    preprocessed: &str,
+    alignment: &EditAlignment,
+    err: TokenizerError,
+) -> TokenizerError {
+    match err {
+        TokenizerError::NoTransition {
+            offset,
+            character,
+            state,
+            partial_output,
+        } => TokenizerError::NoTransition {
+            offset: project_chars(text, preprocessed, alignment, offset..offset + 1).start,
+            character,
+            state,
+            partial_output,
+        },
+        err => err,
     }
 }
 
+/// Project a character range in the preprocessed text onto the original
+/// text.
+fn project_chars(
+    text: &str,
+    preprocessed: &str,
+    alignment: &EditAlignment,
+    chars: Range<usize>,
+) -> Range<usize> {
+    let mut offsets = preprocessed
+        .char_indices()
+        .map(|(idx, _)| idx)
+        .chain(Some(preprocessed.len()))
+        .skip(chars.start);
+    let start = offsets.next().unwrap_or(preprocessed.len());
+    let end = offsets
+        .nth(chars.len().saturating_sub(1))
+        .unwrap_or(preprocessed.len());
+
+    let bytes = alignment.project(start..end);
+    let offset = text[..bytes.start].chars().count();
+    offset..offset + text[bytes].chars().count()
+}
+
 #[cfg(test)]
 mod tests {
     use std::fs::File;
     use std::io::BufReader;
 
+    use regex::Regex;
+
     use super::AlpinoTokenizer;
     use crate::util::str_to_tokens;
-    use crate::Tokenizer;
+    use crate::{Pipeline, RegexRule, TextProcessor, Tokenizer, TokenizerError};
+
+    fn tokenizer() -> AlpinoTokenizer {
+        let read = BufReader::new(File::open("testdata/toy.proto").unwrap());
+        AlpinoTokenizer::from_buf_read(read).unwrap()
+    }
 
     #[test]
     fn test_tokenize() {
-        let read = BufReader::new(File::open("testdata/toy.proto").unwrap());
-        let tokenizer = AlpinoTokenizer::from_buf_read(read).unwrap();
+        let tokenizer = tokenizer();
         assert_eq!(
             tokenizer
                 .tokenize("Dit is een zin. En dit is nog een zin...")
@@ -52,4 +475,292 @@ mod tests {
             str_to_tokens("Dit is een zin .\nEn dit is nog een zin ...")
         );
     }
+
+    #[test]
+    fn test_tokenize_with_offsets() {
+        let tokenizer = tokenizer();
+
+        let text = "Ik koop 1. boter en 2. kaas -zoals gezegd- bij de  Jumbo.";
+        let sentences = tokenizer.tokenize_with_offsets(text).unwrap();
+        assert_eq!(sentences.len(), 1);
+        for token in sentences[0].tokens() {
+            assert_eq!(token.text(), &text[token.span().bytes()]);
+        }
+        assert_eq!(sentences[0].tokens()[13].span().chars(), 51..56);
+
+        let text = "Dit is een zin. En dit is nog een zin...";
+        let sentences = tokenizer.tokenize_with_offsets(text).unwrap();
+        assert_eq!(
+            sentences
+                .iter()
+                .map(|sent| &text[sent.span().bytes()])
+                .collect::<Vec<_>>(),
+            vec!["Dit is een zin.", "En dit is nog een zin..."]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_with_offsets_and_recovery() {
+        let tokenizer = tokenizer();
+
+        let text = "Dit is fo\0ut, zegt hij.";
+        let (sentences, recoveries) = tokenizer.tokenize_with_offsets_and_recovery(text);
+        assert_eq!(recoveries.len(), 1);
+        assert_eq!(
+            sentences[0]
+                .tokens()
+                .iter()
+                .map(|token| (&text[token.span().bytes()], token.space_after()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("Dit", true),
+                ("is", true),
+                ("fo", false),
+                ("\0ut,", true),
+                ("zegt", true),
+                ("hij", false),
+                (".", true)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_without_sentence_splitting() {
+        let tokenizer = tokenizer().with_sentence_splitting(false);
+
+        let text = "Dit is een zin. En dit is nog een zin...";
+        assert_eq!(
+            tokenizer.tokenize(text).unwrap(),
+            str_to_tokens("Dit is een zin . En dit is nog een zin ...")
+        );
+        assert_eq!(
+            tokenizer.tokenize_with_recovery(text).0,
+            tokenizer.tokenize(text).unwrap()
+        );
+        assert_eq!(tokenizer.split_sentences(text).unwrap(), vec![text]);
+
+        let mut session = tokenizer.session();
+        assert_eq!(session.tokenize_text(text).unwrap().n_sentences(), 1);
+    }
+
+    #[test]
+    fn test_enumerations() {
+        let tokenizer = tokenizer();
+
+        for text in [
+            "Ik koop 1. boter en 2. kaas.",
+            "Ik koop a. boter en b. kaas.",
+            "Ik koop i. boter en ii. kaas.",
+        ] {
+            let sentences = tokenizer.tokenize_with_offsets(text).unwrap();
+            assert_eq!(sentences.len(), 1);
+            assert_eq!(sentences[0].tokens().len(), 8);
+            for token in sentences[0].tokens() {
+                assert_eq!(token.text(), &text[token.span().bytes()]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_hashes_in_input() {
+        let tokenizer = tokenizer();
+
+        // Only the markers that are inserted by preprocessing are replaced
+        // by periods.
+        assert_eq!(
+            tokenizer.tokenize("ik programmeer in c# en f# .").unwrap(),
+            str_to_tokens("ik programmeer in c# en f# .")
+        );
+
+        let text = "Ik ken a. c# en b. f#";
+        assert_eq!(
+            tokenizer.tokenize(text).unwrap(),
+            str_to_tokens("Ik ken a. c# en b. f#")
+        );
+        assert_eq!(
+            tokenizer.tokenize_with_recovery(text).0,
+            tokenizer.tokenize(text).unwrap()
+        );
+
+        let sentences = tokenizer.tokenize_with_offsets(text).unwrap();
+        assert_eq!(sentences.len(), 1);
+        for token in sentences[0].tokens() {
+            assert_eq!(token.text(), &text[token.span().bytes()]);
+        }
+    }
+
+    #[test]
+    fn test_pipeline() {
+        let mut tokenizer = tokenizer();
+
+        let text = "AMSTERDAM - De hoofdstad.";
+        assert_eq!(tokenizer.tokenize(text).unwrap().len(), 2);
+
+        assert!(tokenizer
+            .postprocessing_mut()
+            .remove("fix_news_article_opening")
+            .is_some());
+        assert_eq!(tokenizer.tokenize(text).unwrap().len(), 1);
+
+        let text = "Ik koop 1. boter en 2. kaas.";
+        assert_eq!(
+            tokenizer.tokenize(text).unwrap(),
+            str_to_tokens("Ik koop 1. boter en 2. kaas .")
+        );
+        tokenizer
+            .preprocessing_mut()
+            .remove("add_enumeration_markers");
+        assert_ne!(
+            tokenizer.tokenize(text).unwrap(),
+            str_to_tokens("Ik koop 1. boter en 2. kaas .")
+        );
+    }
+
+    #[test]
+    fn test_pipeline_offsets() {
+        let mut tokenizer = tokenizer();
+        tokenizer.preprocessing_mut().push(RegexRule::new(
+            "ellipsis",
+            Regex::new("…").unwrap(),
+            "...",
+        ));
+        tokenizer.postprocessing_mut().push(RegexRule::new(
+            "euro",
+            Regex::new("€ ([0-9]+)").unwrap(),
+            "$1 euro",
+        ));
+
+        let text = "Ik wacht… Dit kost € 10. En dit kost € 200…";
+        let sentences = tokenizer.tokenize_with_offsets(text).unwrap();
+        assert_eq!(
+            sentences
+                .iter()
+                .map(|sent| &text[sent.span().bytes()])
+                .collect::<Vec<_>>(),
+            vec!["Ik wacht…", "Dit kost € 10.", "En dit kost € 200…"]
+        );
+
+        // Text that is inserted by a processor is aligned with an empty
+        // span in the input.
+        let tokens = sentences[2].tokens();
+        assert_eq!(
+            tokens
+                .iter()
+                .map(|token| (token.text(), &text[token.span().bytes()]))
+                .collect::<Vec<_>>(),
+            vec![
+                ("En", "En"),
+                ("dit", "dit"),
+                ("kost", "kost"),
+                ("200", "200"),
+                ("euro", ""),
+                ("...", "…")
+            ]
+        );
+        assert_eq!(tokens[4].span().chars(), 42..42);
+        assert_eq!(tokens[5].span().chars(), 42..43);
+    }
+
+    #[test]
+    fn test_no_transition_offset() {
+        let mut tokenizer = tokenizer();
+        tokenizer.preprocessing_mut().push(RegexRule::new(
+            "ellipsis",
+            Regex::new("…").unwrap(),
+            "...",
+        ));
+
+        // The error offset is in the input, not in the preprocessed text.
+        let text = "Ik wacht… Dit is\0fout.";
+        assert!(matches!(
+            tokenizer.tokenize_text(text),
+            Err(TokenizerError::NoTransition {
+                offset: 16,
+                character: '\0',
+                ..
+            })
+        ));
+        assert!(matches!(
+            tokenizer.tokenize_with_offsets(text),
+            Err(TokenizerError::NoTransition { offset: 16, .. })
+        ));
+        assert!(matches!(
+            tokenizer.session().tokenize_text(text),
+            Err(TokenizerError::NoTransition { offset: 16, .. })
+        ));
+    }
+
+    #[test]
+    fn test_custom_preprocessing_error_offset() {
+        // Processor that does not record its edits.
+        struct Ampersand;
+
+        impl TextProcessor for Ampersand {
+            fn name(&self) -> &str {
+                "ampersand"
+            }
+
+            fn process(&self, text: &str, dst: &mut String) -> bool {
+                dst.clear();
+                dst.push_str(&text.replace('&', "en"));
+                true
+            }
+        }
+
+        let mut tokenizer = tokenizer();
+        *tokenizer.preprocessing_mut() = Pipeline::new();
+        tokenizer.preprocessing_mut().push(Ampersand);
+
+        let text = "Jan & Piet & Klaas zijn\0vrienden.";
+        assert!(matches!(
+            tokenizer.tokenize_text(text),
+            Err(TokenizerError::NoTransition { offset: 23, .. })
+        ));
+        assert!(matches!(
+            tokenizer.tokenize_with_offsets(text),
+            Err(TokenizerError::NoTransition { offset: 23, .. })
+        ));
+    }
+
+    #[test]
+    fn test_split_sentences() {
+        let tokenizer = tokenizer();
+
+        assert_eq!(
+            tokenizer
+                .split_sentences("Dit is  een zin.   En dit is nog een zin...")
+                .unwrap(),
+            vec!["Dit is  een zin.", "En dit is nog een zin..."]
+        );
+        assert_eq!(
+            tokenizer.split_sentences("Ik koop 1. boter.").unwrap(),
+            vec!["Ik koop 1. boter."]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_batch() {
+        let tokenizer = tokenizer();
+
+        let paragraphs = (0..100)
+            .map(|idx| match idx % 3 {
+                0 => format!("Dit is zin {}. En nog een zin.", idx),
+                1 => format!("Ik koop {} 1. boter en 2. kaas.", idx),
+                _ => format!("Fout\0{}", idx),
+            })
+            .collect::<Vec<_>>();
+
+        for n_threads in [0, 1, 4] {
+            let results = tokenizer.tokenize_batch(&paragraphs, n_threads);
+            assert_eq!(results.len(), paragraphs.len());
+            for (paragraph, result) in paragraphs.iter().zip(results) {
+                match tokenizer.tokenize_text(paragraph) {
+                    Ok(tokenized) => assert_eq!(result.unwrap(), tokenized),
+                    Err(_) => assert!(result.is_err()),
+                }
+            }
+        }
+
+        assert!(tokenizer.tokenize_batch::<&str>(&[], 4).is_empty());
+    }
 }
@@ -0,0 +1,71 @@
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+/// Apply `f` to all items using `n_threads` threads.
+///
+/// The results are returned in the order of the items. Items are
+/// distributed dynamically, so that threads that process short items
+/// take on more items. If `n_threads` is zero, the available parallelism
+/// is used.
+///
+/// This function is used by `Tokenizer::tokenize_batch`. It can also be
+/// used to parallelize other work on paragraphs, for instance:
+///
+/// ```
+/// use std::fs::File;
+/// use std::io::BufReader;
+///
+/// use alpino_tokenizer::{map_parallel, AlpinoTokenizer, Tokenizer};
+///
+/// let read = BufReader::new(File::open("testdata/toy.proto").unwrap());
+/// let tokenizer = AlpinoTokenizer::from_buf_read(read).unwrap();
+///
+/// let paragraphs = ["Dit is een zin.", "En dit ook."];
+/// let sentences = map_parallel(&paragraphs, 0, |paragraph| {
+///     tokenizer.tokenize_with_offsets(paragraph).unwrap()
+/// });
+/// assert_eq!(sentences.len(), 2);
+/// ```
+pub fn map_parallel<I, F, T>(items: &[I], n_threads: usize, f: F) -> Vec<T>
+where
+    I: Sync,
+    F: Fn(&I) -> T + Sync,
+    T: Send,
+{
+    let n_threads = match n_threads {
+        0 => thread::available_parallelism().map_or(1, NonZeroUsize::get),
+        n_threads => n_threads,
+    }
+    .min(items.len());
+
+    if n_threads <= 1 {
+        return items.iter().map(f).collect();
+    }
+
+    let next = AtomicUsize::new(0);
+    let mut results = thread::scope(|scope| {
+        let workers = (0..n_threads)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut results = Vec::new();
+                    loop {
+                        let idx = next.fetch_add(1, Ordering::Relaxed);
+                        match items.get(idx) {
+                            Some(item) => results.push((idx, f(item))),
+                            None => return results,
+                        }
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().expect("Tokenizer thread panicked"))
+            .collect::<Vec<_>>()
+    });
+
+    results.sort_unstable_by_key(|&(idx, _)| idx);
+    results.into_iter().map(|(_, result)| result).collect()
+}
@@ -0,0 +1,527 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::state_graph::{BuildError, StateGraph};
+use crate::FiniteStateTokenizer;
+
+/// Builder for tokenizing transducers.
+///
+/// This builder constructs deterministic transducers state by state. Each
+/// transition reads a single character and appends an output string. When
+/// the input ends in a final state, the final output of that state is
+/// appended. States are identified by the numbers that are returned by
+/// [`TransducerBuilder::add_state`].
+///
+/// Characters that do not occur on any transition are *unknown*
+/// characters. Unknown characters are handled by the transitions added
+/// with [`TransducerBuilder::add_unknown_transition`] and
+/// [`TransducerBuilder::add_other_transition`].
+///
+/// Alternatively, the tokenizer can be constructed from pairs of strings
+/// and their outputs using [`TransducerBuilder::add_pair`].
+///
+/// For example, the following tokenizer puts every sentence on its own
+/// line:
+///
+/// ```
+/// use alpino_tokenizer::{TransducerBuilder, Tokenizer};
+///
+/// let mut builder = TransducerBuilder::new();
+/// let start = builder.start_state();
+/// let sentence_end = builder.add_state();
+/// builder.add_unknown_transition(start, "\u{2}", start).unwrap();
+/// builder.add_transition(start, ' ', " ", start).unwrap();
+/// builder.add_transition(start, '.', " .", sentence_end).unwrap();
+/// builder.add_transition(sentence_end, ' ', "\n", start).unwrap();
+/// builder.set_final(start, "").unwrap();
+/// builder.set_final(sentence_end, "").unwrap();
+///
+/// let tokenizer = builder.build();
+/// assert_eq!(
+///   tokenizer.tokenize("Een zin. Nog een zin.").unwrap(),
+///   vec![vec!["Een", "zin", "."], vec!["Nog", "een", "zin", "."]]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct TransducerBuilder {
+    graph: StateGraph,
+    start: usize,
+    pairs: BTreeMap<String, String>,
+}
+
+impl TransducerBuilder {
+    /// Placeholder for unknown characters in outputs.
+    ///
+    /// This placeholder is replaced by the unknown character that was
+    /// read by the last [`TransducerBuilder::add_unknown_transition`]
+    /// transition that was not output yet.
+    pub const UNKNOWN: char = '\u{2}';
+
+    /// Construct a builder with a start state.
+    pub fn new() -> Self {
+        let mut graph = StateGraph::default();
+        let start = graph.add_state();
+        TransducerBuilder {
+            graph,
+            start,
+            pairs: BTreeMap::new(),
+        }
+    }
+
+    /// Get the start state.
+    pub fn start_state(&self) -> usize {
+        self.start
+    }
+
+    /// Add a state.
+    pub fn add_state(&mut self) -> usize {
+        self.graph.add_state()
+    }
+
+    /// Add a transition on `input` from `from` to `to`.
+    ///
+    /// The characters U+0000, U+0001 and U+0002 cannot be used as input,
+    /// since they are the symbols of the sentinel transition and of
+    /// unknown character transitions.
+    pub fn add_transition(
+        &mut self,
+        from: usize,
+        input: char,
+        output: &str,
+        to: usize,
+    ) -> Result<&mut Self, BuildError> {
+        check_input(input)?;
+        self.check_not_pair_start(from)?;
+        self.graph.add_arc(from, input as u32, output, to)?;
+        Ok(self)
+    }
+
+    /// Add a transition on unknown characters from `from` to `to`.
+    ///
+    /// The unknown character is queued, so that it can be output by
+    /// [`TransducerBuilder::UNKNOWN`] placeholders in this or later
+    /// transitions. A state can either have a transition that is added
+    /// by this method or by [`TransducerBuilder::add_other_transition`].
+    pub fn add_unknown_transition(
+        &mut self,
+        from: usize,
+        output: &str,
+        to: usize,
+    ) -> Result<&mut Self, BuildError> {
+        self.check_not_pair_start(from)?;
+        self.graph.add_arc(from, 2, output, to)?;
+        Ok(self)
+    }
+
+    /// Add a transition on unknown characters that are not output.
+    ///
+    /// The unknown character is read without queueing it, so `output`
+    /// should not contain [`TransducerBuilder::UNKNOWN`] placeholders.
+    pub fn add_other_transition(
+        &mut self,
+        from: usize,
+        output: &str,
+        to: usize,
+    ) -> Result<&mut Self, BuildError> {
+        self.check_not_pair_start(from)?;
+        self.graph.add_arc(from, 1, output, to)?;
+        Ok(self)
+    }
+
+    /// Make a state final.
+    ///
+    /// The final output is appended when the input ends in `state`.
+    pub fn set_final(&mut self, state: usize, final_output: &str) -> Result<&mut Self, BuildError> {
+        self.check_not_pair_start(state)?;
+        self.graph.set_final(state, final_output)?;
+        Ok(self)
+    }
+
+    /// Add a string that is rewritten to `output`.
+    ///
+    /// The tokenizer replaces every occurrence of a pair's string by its
+    /// output. Where strings overlap, the leftmost and then the longest
+    /// string is replaced. Other text is copied to the output unchanged.
+    ///
+    /// The transitions of the start state are generated from the pairs
+    /// when the tokenizer is built, so the start state cannot have other
+    /// transitions or a final output. Other states can still have
+    /// transitions to the start state.
+    pub fn add_pair(&mut self, input: &str, output: &str) -> Result<&mut Self, BuildError> {
+        if input.is_empty() {
+            return Err(BuildError::EmptyPair);
+        }
+
+        for ch in input.chars() {
+            check_input(ch)?;
+        }
+
+        if self.pairs.is_empty()
+            && (self.graph.has_arcs(self.start) || self.graph.final_output(self.start).is_some())
+        {
+            return Err(BuildError::PairsOnStartState { state: self.start });
+        }
+
+        match self.pairs.get(input) {
+            Some(current) if current != output => Err(BuildError::ConflictingPair {
+                input: input.to_owned(),
+            }),
+            _ => {
+                self.pairs.insert(input.to_owned(), output.to_owned());
+                Ok(self)
+            }
+        }
+    }
+
+    /// Build the tokenizer.
+    ///
+    /// States that are not reachable from the start state are discarded.
+    pub fn build(mut self) -> FiniteStateTokenizer {
+        if !self.pairs.is_empty() {
+            self.add_pair_states();
+        }
+
+        FiniteStateTokenizer::new(self.graph.into_transducer(self.start))
+    }
+
+    fn check_not_pair_start(&self, state: usize) -> Result<(), BuildError> {
+        if state == self.start && !self.pairs.is_empty() {
+            Err(BuildError::PairsOnStartState { state })
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Add the states and transitions that rewrite the pairs.
+    ///
+    /// Every state corresponds to a string that was read, but not output
+    /// yet, because it is a proper prefix of one of the pairs. The start
+    /// state corresponds to the empty string.
+    fn add_pair_states(&mut self) {
+        let mut prefixes = BTreeSet::new();
+        for input in self.pairs.keys() {
+            prefixes.extend(input.char_indices().map(|(idx, _)| &input[..idx]));
+        }
+
+        let alphabet = self
+            .pairs
+            .keys()
+            .flat_map(|input| input.chars())
+            .collect::<BTreeSet<_>>();
+
+        let mut states = HashMap::new();
+        for &prefix in &prefixes {
+            let state = if prefix.is_empty() {
+                self.start
+            } else {
+                self.graph.add_state()
+            };
+            states.insert(prefix, state);
+        }
+
+        for &prefix in &prefixes {
+            let state = states[prefix];
+
+            for &ch in &alphabet {
+                let mut pending = prefix.to_owned();
+                pending.push(ch);
+                let mut output = String::new();
+                let rest = self.rewrite(&pending, &prefixes, &mut output);
+                self.graph
+                    .add_arc(state, ch as u32, output, states[rest])
+                    .expect("Pair states must not have other transitions");
+            }
+
+            // Unknown characters are never part of a pair, so the pending
+            // string is rewritten completely.
+            let mut output = String::new();
+            self.rewrite(prefix, &BTreeSet::new(), &mut output);
+            self.graph
+                .set_final(state, output.clone())
+                .expect("Pair states must not have a final output");
+            output.push(Self::UNKNOWN);
+            self.graph
+                .add_arc(state, 2, output, self.start)
+                .expect("Pair states must not have other transitions");
+        }
+    }
+
+    /// Rewrite the pending input until it is one of the `prefixes`.
+    ///
+    /// Returns the part of the input that was not rewritten.
+    fn rewrite<'a>(
+        &self,
+        mut pending: &'a str,
+        prefixes: &BTreeSet<&str>,
+        output: &mut String,
+    ) -> &'a str {
+        while !pending.is_empty() && !prefixes.contains(pending) {
+            // The longest pair at the start of the input. Pairs cannot be
+            // longer than the input, since it is not a prefix of any pair.
+            let longest = pending
+                .char_indices()
+                .map(|(idx, ch)| idx + ch.len_utf8())
+                .rev()
+                .find_map(|len| self.pairs.get(&pending[..len]).map(|output| (len, output)));
+
+            let len = match longest {
+                Some((len, pair_output)) => {
+                    output.push_str(pair_output);
+                    len
+                }
+                None => {
+                    let ch = pending.chars().next().unwrap();
+                    output.push(ch);
+                    ch.len_utf8()
+                }
+            };
+
+            pending = &pending[len..];
+        }
+
+        pending
+    }
+}
+
+fn check_input(input: char) -> Result<(), BuildError> {
+    match input {
+        '\0' | '\u{1}' | '\u{2}' => Err(BuildError::ReservedCharacter { character: input }),
+        _ => Ok(()),
+    }
+}
+
+impl Default for TransducerBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TransducerBuilder;
+    use crate::state_graph::BuildError;
+    use crate::transducer::Transducer;
+    use crate::{FiniteStateTokenizer, Tokenizer};
+
+    fn sentence_tokenizer() -> FiniteStateTokenizer {
+        let mut builder = TransducerBuilder::new();
+        let start = builder.start_state();
+        let word = builder.add_state();
+        let end = builder.add_state();
+        builder
+            .add_unknown_transition(start, "\u{2}", word)
+            .unwrap()
+            .add_unknown_transition(word, "\u{2}", word)
+            .unwrap()
+            .add_transition(word, ' ', " ", start)
+            .unwrap()
+            .add_transition(word, '.', " .", end)
+            .unwrap()
+            .add_transition(word, ',', " , ", start)
+            .unwrap()
+            .add_transition(end, ' ', "\n", start)
+            .unwrap()
+            .add_transition(start, ' ', "", start)
+            .unwrap()
+            .set_final(word, "")
+            .unwrap()
+            .set_final(end, "")
+            .unwrap();
+        builder.build()
+    }
+
+    #[test]
+    fn builds_sorted_layout() {
+        let tokenizer = sentence_tokenizer();
+        assert_eq!(tokenizer.validate(), Ok(()));
+
+        let transitions = match tokenizer.transducer() {
+            Transducer::Owned { transitions, .. } => transitions,
+            Transducer::Compact(_) => unreachable!(),
+        };
+
+        let layout = transitions
+            .iter()
+            .map(|t| (t.symbol, t.is_last_of_state, t.next))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            layout,
+            vec![
+                (0, true, 0),
+                (0, false, 2),
+                // Start state.
+                (2, false, 4),
+                (' ' as u32, true, 2),
+                // Word state.
+                (2, false, 4),
+                (' ' as u32, false, 2),
+                (',' as u32, false, 2),
+                ('.' as u32, true, 8),
+                // Sentence end state.
+                (' ' as u32, true, 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn tokenizes_with_unknown_characters() {
+        let tokenizer = sentence_tokenizer();
+        assert_eq!(
+            tokenizer.tokenize("Hallo, wereld.  Tot ziens.").unwrap(),
+            vec![vec!["Hallo", ",", "wereld", "."], vec!["Tot", "ziens", "."]]
+        );
+    }
+
+    #[test]
+    fn other_transitions_do_not_output() {
+        let mut builder = TransducerBuilder::new();
+        let start = builder.start_state();
+        builder
+            .add_other_transition(start, "", start)
+            .unwrap()
+            .add_transition(start, 'a', "a", start)
+            .unwrap()
+            .set_final(start, "")
+            .unwrap();
+        let tokenizer = builder.build();
+        assert_eq!(tokenizer.validate(), Ok(()));
+        assert_eq!(tokenizer.tokenize_raw("xaya".chars()).unwrap(), "aa");
+    }
+
+    #[test]
+    fn pairs() {
+        let mut builder = TransducerBuilder::new();
+        builder
+            .add_pair("dr", "DR")
+            .unwrap()
+            .add_pair("dr.", "dokter")
+            .unwrap()
+            .add_pair("drs.", "doctorandus")
+            .unwrap();
+        let tokenizer = builder.build();
+
+        assert_eq!(tokenizer.validate(), Ok(()));
+        assert_eq!(
+            tokenizer
+                .tokenize_raw("de drs. en dr. drinken, drs".chars())
+                .unwrap(),
+            "de doctorandus en dokter DRinken, DRs"
+        );
+        assert_eq!(tokenizer.tokenize_raw("ddr.".chars()).unwrap(), "ddokter");
+        assert_eq!(tokenizer.tokenize_raw("".chars()).unwrap(), "");
+    }
+
+    #[test]
+    fn pairs_with_states() {
+        // Other states can return to the start state of the pairs.
+        let mut builder = TransducerBuilder::new();
+        let start = builder.start_state();
+        let quote = builder.add_state();
+        builder
+            .add_pair("'", "")
+            .unwrap()
+            .add_pair("'t", "het")
+            .unwrap()
+            .add_transition(quote, 'x', "y", start)
+            .unwrap();
+        assert_eq!(
+            builder.add_transition(start, 'x', "y", quote).unwrap_err(),
+            BuildError::PairsOnStartState { state: start }
+        );
+        assert_eq!(
+            builder.set_final(start, "").unwrap_err(),
+            BuildError::PairsOnStartState { state: start }
+        );
+
+        let tokenizer = builder.build();
+        assert_eq!(tokenizer.validate(), Ok(()));
+        assert_eq!(
+            tokenizer.tokenize_raw("'t is 'n zin".chars()).unwrap(),
+            "het is n zin"
+        );
+    }
+
+    #[test]
+    fn rejects_conflicts() {
+        let mut builder = TransducerBuilder::new();
+        let start = builder.start_state();
+        builder.add_transition(start, 'a', "a", start).unwrap();
+        assert_eq!(
+            builder.add_transition(start, 'a', "b", start).unwrap_err(),
+            BuildError::DuplicateSymbol {
+                state: 0,
+                symbol: 'a' as u32
+            }
+        );
+
+        builder.add_unknown_transition(start, "", start).unwrap();
+        assert_eq!(
+            builder.add_other_transition(start, "", start).unwrap_err(),
+            BuildError::ConflictingUnknownSymbols { state: 0 }
+        );
+
+        assert_eq!(
+            builder
+                .add_transition(start, '\u{2}', "", start)
+                .unwrap_err(),
+            BuildError::ReservedCharacter { character: '\u{2}' }
+        );
+
+        assert_eq!(
+            builder.add_pair("b", "x").unwrap_err(),
+            BuildError::PairsOnStartState { state: 0 }
+        );
+
+        // A transition on U+0000 would precede the transition for
+        // unknown characters, so unknown characters would be rejected.
+        let mut builder = TransducerBuilder::new();
+        let start = builder.start_state();
+        builder.add_other_transition(start, "?", start).unwrap();
+        assert_eq!(
+            builder.add_transition(start, '\0', "", start).unwrap_err(),
+            BuildError::ReservedCharacter { character: '\0' }
+        );
+        assert_eq!(builder.build().tokenize_raw("é".chars()).unwrap(), "?");
+
+        let mut builder = TransducerBuilder::new();
+        builder
+            .add_pair("b", "x")
+            .unwrap()
+            .add_pair("b", "x")
+            .unwrap();
+        assert_eq!(
+            builder.add_pair("b", "y").unwrap_err(),
+            BuildError::ConflictingPair {
+                input: "b".to_owned()
+            }
+        );
+        assert_eq!(
+            builder.add_pair("", "y").unwrap_err(),
+            BuildError::EmptyPair
+        );
+        assert_eq!(
+            builder.add_pair("c\u{1}", "y").unwrap_err(),
+            BuildError::ReservedCharacter { character: '\u{1}' }
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_states() {
+        let mut builder = TransducerBuilder::new();
+        let start = builder.start_state();
+        assert_eq!(
+            builder.add_transition(start, 'a', "a", 1).unwrap_err(),
+            BuildError::UnknownState { state: 1 }
+        );
+        assert_eq!(
+            builder.add_unknown_transition(1, "", start).unwrap_err(),
+            BuildError::UnknownState { state: 1 }
+        );
+        assert_eq!(
+            builder.add_other_transition(2, "", start).unwrap_err(),
+            BuildError::UnknownState { state: 2 }
+        );
+        assert_eq!(
+            builder.set_final(3, "").unwrap_err(),
+            BuildError::UnknownState { state: 3 }
+        );
+    }
+}
@@ -0,0 +1,557 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::io::Write;
+use std::ops::Deref;
+use std::str;
+
+use memmap2::Mmap;
+
+use crate::transducer::{Transducer, NO_ASCII_TABLE};
+use crate::TokenizerError;
+
+// The compact format consists of a header, followed by the transitions,
+// the ASCII tables of the transition index, the alphabet, and the output
+// string pool.
+// All integers are stored in little endian byte order.
+//
+// Header:
+//
+// - magic (8 bytes)
+// - format version (u32)
+// - number of transitions (u32)
+// - number of ASCII tables (u32)
+// - number of alphabet symbols (u32)
+// - string pool length in bytes (u32)
+//
+// Transition (8 x u32):
+//
+// - symbol
+// - offset of the next state
+// - flags
+// - output offset in the string pool
+// - final output offset in the string pool
+// - output length (lower 16 bits) and final output length (upper 16 bits)
+// - offset after the last transition of the state
+// - ASCII table index, u32::MAX if the state does not have a table
+//
+// ASCII table (128 x u16): for each ASCII symbol, the offset of the
+// transition relative to the state plus one, zero if there is no
+// transition.
+//
+// Alphabet (u32 per symbol): the symbols of the transitions, sorted and
+// without duplicates.
+
+pub(crate) const MAGIC: &[u8; 8] = b"ALPTOKFT";
+const VERSION: u32 = 2;
+
+const HEADER_LEN: usize = 28;
+const TRANSITION_LEN: usize = 32;
+const ASCII_TABLE_LEN: usize = 256;
+const SYMBOL_LEN: usize = 4;
+
+const SYMBOL: usize = 0;
+const NEXT: usize = 4;
+const FLAGS: usize = 8;
+const OUTPUT_OFFSET: usize = 12;
+const FINAL_OUTPUT_OFFSET: usize = 16;
+const OUTPUT_LENS: usize = 20;
+const RUN_END: usize = 24;
+const ASCII_TABLE: usize = 28;
+
+const IS_LAST_OF_STATE: u32 = 1;
+const IS_FINAL_STATE: u32 = 2;
+
+/// Backing storage of a compact transducer.
+pub(crate) enum CompactData {
+    Bytes(Cow<'static, [u8]>),
+    Mmap(Mmap),
+}
+
+impl Deref for CompactData {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            CompactData::Bytes(bytes) => bytes,
+            CompactData::Mmap(mmap) => mmap,
+        }
+    }
+}
+
+/// Transducer that uses the compact format without copying.
+pub(crate) struct CompactTransducer {
+    data: CompactData,
+    len: usize,
+    tables_offset: usize,
+    alphabet_offset: usize,
+    pool_offset: usize,
+}
+
+impl CompactTransducer {
+    pub fn new(data: CompactData) -> Result<Self, TokenizerError> {
+        if data.len() < HEADER_LEN || &data[..MAGIC.len()] != MAGIC {
+            return Err(invalid("missing compact transducer header"));
+        }
+
+        let version = read_u32(&data, 8);
+        if version != VERSION {
+            return Err(invalid(format!("unsupported version {}", version)));
+        }
+
+        let len = read_u32(&data, 12) as usize;
+        let n_tables = read_u32(&data, 16) as usize;
+        let alphabet_len = read_u32(&data, 20) as usize;
+        let pool_len = read_u32(&data, 24) as usize;
+
+        // The walker starts with the transition to the start state.
+        if len < 2 {
+            return Err(invalid(format!(
+                "transducer has {} transitions, at least 2 are required",
+                len
+            )));
+        }
+
+        let overflow = || invalid("section lengths overflow");
+        let tables_offset = section_end(HEADER_LEN, len, TRANSITION_LEN).ok_or_else(overflow)?;
+        let alphabet_offset =
+            section_end(tables_offset, n_tables, ASCII_TABLE_LEN).ok_or_else(overflow)?;
+        let pool_offset =
+            section_end(alphabet_offset, alphabet_len, SYMBOL_LEN).ok_or_else(overflow)?;
+        let data_len = pool_offset.checked_add(pool_len).ok_or_else(overflow)?;
+        if data.len() != data_len {
+            return Err(invalid(format!(
+                "expected {} bytes, got {}",
+                data_len,
+                data.len()
+            )));
+        }
+
+        let transducer = CompactTransducer {
+            data,
+            len,
+            tables_offset,
+            alphabet_offset,
+            pool_offset,
+        };
+
+        transducer.check(n_tables)?;
+
+        Ok(transducer)
+    }
+
+    // Check that the outputs and indices can be used safely.
+    fn check(&self, n_tables: usize) -> Result<(), TokenizerError> {
+        let pool = str::from_utf8(&self.data[self.pool_offset..])
+            .map_err(|err| invalid(format!("string pool is not valid UTF-8: {}", err)))?;
+
+        let alphabet = self.alphabet().collect::<Vec<_>>();
+        if alphabet.windows(2).any(|pair| pair[0] >= pair[1]) {
+            return Err(invalid("alphabet is not sorted"));
+        }
+
+        for idx in 0..self.len {
+            let lens = self.field(idx, OUTPUT_LENS);
+            for &(offset, len) in &[
+                (self.field(idx, OUTPUT_OFFSET), lens & 0xffff),
+                (self.field(idx, FINAL_OUTPUT_OFFSET), lens >> 16),
+            ] {
+                let start = offset as usize;
+                let valid = match start.checked_add(len as usize) {
+                    Some(end) => {
+                        end <= pool.len()
+                            && pool.is_char_boundary(start)
+                            && pool.is_char_boundary(end)
+                    }
+                    None => false,
+                };
+                if !valid {
+                    return Err(invalid(format!("invalid output in transition {}", idx)));
+                }
+            }
+
+            // States are identified by their first transition, so the next
+            // state of a transition must be a transition of the transducer.
+            if self.next(idx) as usize >= self.len {
+                return Err(invalid(format!("invalid next state in transition {}", idx)));
+            }
+
+            let run_end = self.field(idx, RUN_END) as usize;
+            if run_end <= idx || run_end > self.len {
+                return Err(invalid(format!("invalid state end in transition {}", idx)));
+            }
+
+            let table = self.field(idx, ASCII_TABLE);
+            if table == NO_ASCII_TABLE {
+                continue;
+            }
+
+            if table as usize >= n_tables {
+                return Err(invalid(format!(
+                    "invalid ASCII table in transition {}",
+                    idx
+                )));
+            }
+
+            for symbol in 0..128 {
+                let offset = self.ascii_table_entry(table, symbol) as usize;
+                if offset != 0 && idx + offset > run_end {
+                    return Err(invalid(format!("invalid ASCII table {}", table)));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn symbol(&self, idx: usize) -> u32 {
+        self.field(idx, SYMBOL)
+    }
+
+    pub fn is_last_of_state(&self, idx: usize) -> bool {
+        self.field(idx, FLAGS) & IS_LAST_OF_STATE != 0
+    }
+
+    pub fn is_final_state(&self, idx: usize) -> bool {
+        self.field(idx, FLAGS) & IS_FINAL_STATE != 0
+    }
+
+    pub fn next(&self, idx: usize) -> u32 {
+        self.field(idx, NEXT)
+    }
+
+    pub fn output(&self, idx: usize) -> &str {
+        let len = self.field(idx, OUTPUT_LENS) & 0xffff;
+        self.pool_str(self.field(idx, OUTPUT_OFFSET), len)
+    }
+
+    pub fn final_output(&self, idx: usize) -> &str {
+        let len = self.field(idx, OUTPUT_LENS) >> 16;
+        self.pool_str(self.field(idx, FINAL_OUTPUT_OFFSET), len)
+    }
+
+    pub fn find_transition(&self, state: usize, symbol: u32) -> Option<usize> {
+        let table = self.field(state, ASCII_TABLE);
+        if symbol < 128 && table != NO_ASCII_TABLE {
+            return match self.ascii_table_entry(table, symbol) {
+                0 => None,
+                offset => Some(state + offset as usize - 1),
+            };
+        }
+
+        let mut low = state;
+        let mut high = self.field(state, RUN_END) as usize;
+        while low < high {
+            let mid = low + (high - low) / 2;
+            let mid_symbol = self.symbol(mid);
+            if mid_symbol < symbol {
+                low = mid + 1;
+            } else if mid_symbol > symbol {
+                high = mid;
+            } else {
+                return Some(mid);
+            }
+        }
+
+        None
+    }
+
+    /// Get the alphabet that is stored in the transducer.
+    pub fn alphabet(&self) -> impl Iterator<Item = u32> + '_ {
+        self.data[self.alphabet_offset..self.pool_offset]
+            .chunks_exact(SYMBOL_LEN)
+            .map(|symbol| u32::from_le_bytes(symbol.try_into().unwrap()))
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    fn ascii_table_entry(&self, table: u32, symbol: u32) -> u16 {
+        let offset = self.tables_offset + table as usize * ASCII_TABLE_LEN + symbol as usize * 2;
+        u16::from_le_bytes(self.data[offset..offset + 2].try_into().unwrap())
+    }
+
+    fn field(&self, idx: usize, field: usize) -> u32 {
+        read_u32(&self.data, HEADER_LEN + idx * TRANSITION_LEN + field)
+    }
+
+    fn pool_str(&self, offset: u32, len: u32) -> &str {
+        let start = self.pool_offset + offset as usize;
+        let bytes = &self.data[start..start + len as usize];
+
+        // This is safe, since the string pool and output boundaries were
+        // checked when the transducer was constructed.
+        unsafe { str::from_utf8_unchecked(bytes) }
+    }
+}
+
+/// Write a transducer in the compact format.
+pub(crate) fn write_compact<W>(transducer: &Transducer, mut write: W) -> Result<(), TokenizerError>
+where
+    W: Write,
+{
+    let (transitions, index) = match transducer {
+        Transducer::Owned { transitions, index } => (transitions, index),
+        Transducer::Compact(compact) => {
+            return write
+                .write_all(compact.data())
+                .map_err(TokenizerError::WriteError)
+        }
+    };
+
+    // Outputs are stored once in the string pool.
+    let mut pool = String::new();
+    let mut pool_offsets = HashMap::new();
+    let mut add_to_pool = |s: &str| -> Result<(u32, u32), TokenizerError> {
+        if s.len() > u16::MAX as usize {
+            return Err(invalid(format!("output too long: {}", s)));
+        }
+
+        let offset = *pool_offsets.entry(s.to_owned()).or_insert_with(|| {
+            let offset = pool.len() as u32;
+            pool.push_str(s);
+            offset
+        });
+
+        Ok((offset, s.len() as u32))
+    };
+
+    let alphabet = transducer.alphabet();
+
+    let mut data = Vec::with_capacity(
+        HEADER_LEN
+            + transitions.len() * TRANSITION_LEN
+            + index.ascii_tables.len() * ASCII_TABLE_LEN
+            + alphabet.len() * SYMBOL_LEN,
+    );
+    data.extend_from_slice(MAGIC);
+    for value in &[
+        VERSION,
+        transitions.len() as u32,
+        index.ascii_tables.len() as u32,
+        alphabet.len() as u32,
+        0,
+    ] {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+
+    for (idx, transition) in transitions.iter().enumerate() {
+        let (output_offset, output_len) = add_to_pool(&transition.output)?;
+        let (final_output_offset, final_output_len) = add_to_pool(&transition.final_output)?;
+
+        let mut flags = 0;
+        if transition.is_last_of_state {
+            flags |= IS_LAST_OF_STATE;
+        }
+        if transition.is_final_state {
+            flags |= IS_FINAL_STATE;
+        }
+
+        for value in &[
+            transition.symbol,
+            transition.next,
+            flags,
+            output_offset,
+            final_output_offset,
+            output_len | (final_output_len << 16),
+            index.run_ends[idx],
+            index.ascii_table_indices[idx],
+        ] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+
+    for table in &index.ascii_tables {
+        for entry in table {
+            data.extend_from_slice(&entry.to_le_bytes());
+        }
+    }
+
+    for symbol in alphabet {
+        data.extend_from_slice(&symbol.to_le_bytes());
+    }
+
+    data[24..28].copy_from_slice(&(pool.len() as u32).to_le_bytes());
+
+    write
+        .write_all(&data)
+        .and_then(|_| write.write_all(pool.as_bytes()))
+        .map_err(TokenizerError::WriteError)
+}
+
+fn invalid(message: impl Into<String>) -> TokenizerError {
+    TokenizerError::CompactFormatError(message.into())
+}
+
+/// Get the end of a section of `n` items of `item_len` bytes that starts
+/// at `offset`, `None` if it overflows.
+fn section_end(offset: usize, n: usize, item_len: usize) -> Option<usize> {
+    n.checked_mul(item_len)?.checked_add(offset)
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::fs::{self, File};
+    use std::io::BufReader;
+
+    use super::read_u32;
+    use crate::transducer::Transducer;
+    use crate::{FiniteStateTokenizer, Tokenizer, TokenizerError};
+
+    fn tokenizer() -> FiniteStateTokenizer {
+        let read = BufReader::new(File::open("testdata/toy.proto").unwrap());
+        FiniteStateTokenizer::from_buf_read(read).unwrap()
+    }
+
+    fn compact_data() -> Vec<u8> {
+        let mut data = Vec::new();
+        tokenizer().write_compact(&mut data).unwrap();
+        data
+    }
+
+    #[test]
+    fn compact_transducer_is_equal_to_protobuf() {
+        let tokenizer = tokenizer();
+        let compact = FiniteStateTokenizer::from_compact(compact_data()).unwrap();
+        assert!(matches!(compact.transducer(), Transducer::Compact(_)));
+
+        assert_eq!(compact.transducer().len(), tokenizer.transducer().len());
+        for idx in 0..tokenizer.transducer().len() {
+            assert_eq!(
+                compact.transducer().symbol(idx),
+                tokenizer.transducer().symbol(idx)
+            );
+            assert_eq!(
+                compact.transducer().next(idx),
+                tokenizer.transducer().next(idx)
+            );
+            assert_eq!(
+                compact.transducer().output(idx),
+                tokenizer.transducer().output(idx)
+            );
+            assert_eq!(
+                compact.transducer().final_output(idx),
+                tokenizer.transducer().final_output(idx)
+            );
+        }
+
+        assert_eq!(
+            compact.transducer().alphabet(),
+            tokenizer.transducer().alphabet()
+        );
+
+        let states = (0..tokenizer.transducer().len())
+            .map(|idx| tokenizer.transducer().next(idx) as usize)
+            .collect::<HashSet<_>>();
+        for state in states {
+            for symbol in (0..300).chain([8203, 65279, 70000]) {
+                assert_eq!(
+                    compact.transducer().find_transition(state, symbol),
+                    tokenizer.transducer().find_transition(state, symbol)
+                );
+            }
+        }
+
+        let text = "Dit is een zin. En dit is nog een zin... Één zin, één 🦀!";
+        assert_eq!(
+            compact.tokenize(text).unwrap(),
+            tokenizer.tokenize(text).unwrap()
+        );
+    }
+
+    #[test]
+    fn write_compact_transducer() {
+        let data = compact_data();
+        let compact = FiniteStateTokenizer::from_compact(data.clone()).unwrap();
+        let mut rewritten = Vec::new();
+        compact.write_compact(&mut rewritten).unwrap();
+        assert_eq!(rewritten, data);
+    }
+
+    #[test]
+    fn from_file_detects_format() {
+        let path =
+            std::env::temp_dir().join(format!("alpino-tokenizer-{}.fst", std::process::id()));
+        fs::write(&path, compact_data()).unwrap();
+        let compact = FiniteStateTokenizer::from_file(&path);
+        fs::remove_file(&path).unwrap();
+        assert!(matches!(
+            compact.unwrap().transducer(),
+            Transducer::Compact(_)
+        ));
+
+        let protobuf = FiniteStateTokenizer::from_file("testdata/toy.proto").unwrap();
+        assert!(matches!(protobuf.transducer(), Transducer::Owned { .. }));
+    }
+
+    #[test]
+    fn rejects_invalid_data() {
+        let data = compact_data();
+
+        let mut bad_magic = data.clone();
+        bad_magic[0] = b'X';
+        assert!(matches!(
+            FiniteStateTokenizer::from_compact(bad_magic),
+            Err(TokenizerError::CompactFormatError(_))
+        ));
+
+        assert!(matches!(
+            FiniteStateTokenizer::from_compact(data[..data.len() - 1].to_vec()),
+            Err(TokenizerError::CompactFormatError(_))
+        ));
+
+        let mut bad_next = data.clone();
+        let next = super::HEADER_LEN + 3 * super::TRANSITION_LEN + super::NEXT;
+        bad_next[next..next + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(matches!(
+            FiniteStateTokenizer::from_compact(bad_next),
+            Err(TokenizerError::CompactFormatError(_))
+        ));
+
+        // Swap the first two symbols of the alphabet.
+        let mut bad_alphabet = data.clone();
+        let alphabet = super::HEADER_LEN
+            + read_u32(&data, 12) as usize * super::TRANSITION_LEN
+            + read_u32(&data, 16) as usize * super::ASCII_TABLE_LEN;
+        bad_alphabet[alphabet..alphabet + 8].rotate_left(4);
+        assert!(matches!(
+            FiniteStateTokenizer::from_compact(bad_alphabet),
+            Err(TokenizerError::CompactFormatError(_))
+        ));
+
+        // The walker needs the sentinel and the transition to the start
+        // state.
+        let mut too_short = data.clone();
+        too_short[12..16].copy_from_slice(&1u32.to_le_bytes());
+        assert!(matches!(
+            FiniteStateTokenizer::from_compact(too_short),
+            Err(TokenizerError::CompactFormatError(_))
+        ));
+
+        let mut huge_sections = data.clone();
+        for field in [12, 16, 20, 24] {
+            huge_sections[field..field + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        }
+        assert!(matches!(
+            FiniteStateTokenizer::from_compact(huge_sections),
+            Err(TokenizerError::CompactFormatError(_))
+        ));
+
+        let mut bad_version = data;
+        bad_version[8] = 42;
+        assert!(matches!(
+            FiniteStateTokenizer::from_compact(bad_version),
+            Err(TokenizerError::CompactFormatError(_))
+        ));
+    }
+}
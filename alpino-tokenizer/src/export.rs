@@ -0,0 +1,437 @@
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io::Write;
+
+use crate::state_graph::DEAD_SYMBOL;
+use crate::transducer::Transducer;
+use crate::TokenizerError;
+
+/// Text format for exporting a transducer.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ExportFormat {
+    /// AT&T (OpenFST) text format.
+    ///
+    /// Every line is a transition with the source state, target state,
+    /// input symbol, and output, separated by tabs. Final states are
+    /// listed on separate lines. Final outputs are written as transitions
+    /// on the empty input to an additional final state.
+    Att,
+
+    /// Graphviz DOT format.
+    Dot,
+}
+
+/// Part of a transducer to export.
+///
+/// The default subgraph contains all states that are reachable from
+/// the start state.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Subgraph {
+    /// State to start from, the start state of the transducer if `None`.
+    pub state: Option<usize>,
+
+    /// Maximum number of transitions to follow from the state.
+    pub depth: Option<usize>,
+}
+
+pub(crate) fn export<W>(
+    transducer: &Transducer,
+    mut write: W,
+    format: ExportFormat,
+    subgraph: Subgraph,
+) -> Result<(), TokenizerError>
+where
+    W: Write,
+{
+    let graph = Graph::new(transducer, subgraph)?;
+    match format {
+        ExportFormat::Att => graph.write_att(&mut write),
+        ExportFormat::Dot => graph.write_dot(&mut write),
+    }
+    .map_err(TokenizerError::WriteError)
+}
+
+/// States of the subgraph that is exported.
+struct Graph<'a> {
+    transducer: &'a Transducer,
+
+    root: usize,
+
+    /// States in breadth-first order, with a flag that indicates whether
+    /// the transitions of the state should be exported.
+    states: Vec<(usize, bool)>,
+
+    /// For every state, a transition that leads to the state.
+    ///
+    /// Final states and final outputs are stored in transitions. Both
+    /// formats can only represent them as properties of states, so
+    /// `new` verifies that all transitions to a state agree on them.
+    incoming: HashMap<usize, usize>,
+}
+
+impl<'a> Graph<'a> {
+    fn new(transducer: &'a Transducer, subgraph: Subgraph) -> Result<Self, TokenizerError> {
+        // Skip the sentinel transition and the transitions of states
+        // without outgoing transitions, which never lead anywhere.
+        let mut incoming = HashMap::new();
+        for transition in 1..transducer.len() {
+            if transducer.symbol(transition) == DEAD_SYMBOL {
+                continue;
+            }
+
+            let state = transducer.next(transition) as usize;
+            let first = *incoming.entry(state).or_insert(transition);
+            if final_output(transducer, first) != final_output(transducer, transition) {
+                return Err(TokenizerError::ConflictingFinalOutput(state));
+            }
+        }
+
+        let root = match subgraph.state {
+            Some(state) => state,
+            None if transducer.len() > 1 => transducer.next(1) as usize,
+            None => return Err(TokenizerError::NoSuchState(1)),
+        };
+        if !incoming.contains_key(&root) {
+            return Err(TokenizerError::NoSuchState(root));
+        }
+
+        let mut states = Vec::new();
+        let mut seen = HashSet::new();
+        seen.insert(root);
+        let mut agenda = VecDeque::new();
+        agenda.push_back((root, 0));
+
+        while let Some((state, depth)) = agenda.pop_front() {
+            let expand = subgraph.depth.map(|max| depth < max).unwrap_or(true);
+            states.push((state, expand));
+
+            if !expand {
+                continue;
+            }
+
+            for transition in arcs(transducer, state) {
+                let next = transducer.next(transition) as usize;
+                if seen.insert(next) {
+                    agenda.push_back((next, depth + 1));
+                }
+            }
+        }
+
+        Ok(Graph {
+            transducer,
+            root,
+            states,
+            incoming,
+        })
+    }
+
+    fn final_output(&self, state: usize) -> Option<&str> {
+        final_output(self.transducer, self.incoming[&state])
+    }
+
+    fn write_att(&self, write: &mut impl Write) -> std::io::Result<()> {
+        // Target of the transitions for final outputs. This number is
+        // never used as a state, since states are transition offsets.
+        let final_state = self.transducer.len();
+        let mut has_final_state = false;
+
+        for &(state, expand) in &self.states {
+            if expand {
+                for transition in arcs(self.transducer, state) {
+                    writeln!(
+                        write,
+                        "{}\t{}\t{}\t{}",
+                        state,
+                        self.transducer.next(transition),
+                        symbol_label(self.transducer.symbol(transition)),
+                        output_label(self.transducer.output(transition))
+                    )?;
+                }
+            }
+
+            match self.final_output(state) {
+                Some("") => writeln!(write, "{}", state)?,
+                Some(final_output) => {
+                    has_final_state = true;
+                    writeln!(
+                        write,
+                        "{}\t{}\t<eps>\t{}",
+                        state,
+                        final_state,
+                        output_label(final_output)
+                    )?
+                }
+                None => (),
+            }
+        }
+
+        if has_final_state {
+            writeln!(write, "{}", final_state)?;
+        }
+
+        Ok(())
+    }
+
+    fn write_dot(&self, write: &mut impl Write) -> std::io::Result<()> {
+        writeln!(write, "digraph transducer {{")?;
+        writeln!(write, "  rankdir=LR;")?;
+        writeln!(write, "  node [shape=circle];")?;
+        writeln!(write, "  start [shape=point];")?;
+        writeln!(write, "  start -> {};", self.root)?;
+
+        for &(state, expand) in &self.states {
+            let (shape, label) = match self.final_output(state) {
+                Some("") => ("doublecircle", state.to_string()),
+                Some(final_output) => (
+                    "doublecircle",
+                    format!("{}\n/{}", state, output_label(final_output)),
+                ),
+                None => ("circle", state.to_string()),
+            };
+
+            // States of which the transitions are not exported are dashed.
+            let style = if expand { "solid" } else { "dashed" };
+
+            writeln!(
+                write,
+                "  {} [shape={}, style={}, label=\"{}\"];",
+                state,
+                shape,
+                style,
+                escape_dot(&label)
+            )?;
+        }
+
+        for &(state, expand) in &self.states {
+            if !expand {
+                continue;
+            }
+
+            for transition in arcs(self.transducer, state) {
+                let label = format!(
+                    "{}:{}",
+                    symbol_label(self.transducer.symbol(transition)),
+                    output_label(self.transducer.output(transition))
+                );
+                writeln!(
+                    write,
+                    "  {} -> {} [label=\"{}\"];",
+                    state,
+                    self.transducer.next(transition),
+                    escape_dot(&label)
+                )?;
+            }
+        }
+
+        writeln!(write, "}}")
+    }
+}
+
+/// Final output of the state that a transition leads to.
+fn final_output(transducer: &Transducer, transition: usize) -> Option<&str> {
+    if transducer.is_final_state(transition) {
+        Some(transducer.final_output(transition))
+    } else {
+        None
+    }
+}
+
+/// Transitions of a state that can be taken.
+fn arcs(transducer: &Transducer, state: usize) -> impl Iterator<Item = usize> + '_ {
+    transducer
+        .state_transitions(state)
+        .filter(move |&transition| transducer.symbol(transition) != DEAD_SYMBOL)
+}
+
+/// Readable label for an input symbol.
+///
+/// Symbol 1 matches an unknown character that is not output, symbol 2
+/// an unknown character that is output through the `<unk>` placeholder.
+pub(crate) fn symbol_label(symbol: u32) -> Cow<'static, str> {
+    match symbol {
+        1 => Cow::Borrowed("<other>"),
+        2 => Cow::Borrowed("<unk>"),
+        _ => match char::from_u32(symbol) {
+            Some(ch) => char_label(ch),
+            None => Cow::Owned(format!("<U+{:04X}>", symbol)),
+        },
+    }
+}
+
+/// Readable label for an output string.
+pub(crate) fn output_label(output: &str) -> Cow<'_, str> {
+    if output.is_empty() {
+        return Cow::Borrowed("<eps>");
+    }
+
+    if !output.chars().any(|ch| ch == '\u{2}' || needs_escape(ch)) {
+        return Cow::Borrowed(output);
+    }
+
+    Cow::Owned(
+        output
+            .chars()
+            .map(|ch| match ch {
+                '\u{2}' => Cow::Borrowed("<unk>"),
+                _ => char_label(ch),
+            })
+            .collect(),
+    )
+}
+
+fn char_label(ch: char) -> Cow<'static, str> {
+    match ch {
+        ' ' => Cow::Borrowed("<space>"),
+        '\t' => Cow::Borrowed("<tab>"),
+        '\n' => Cow::Borrowed("<newline>"),
+        ch if needs_escape(ch) => Cow::Owned(format!("<U+{:04X}>", ch as u32)),
+        ch => Cow::Owned(ch.to_string()),
+    }
+}
+
+fn needs_escape(ch: char) -> bool {
+    ch.is_whitespace() || ch.is_control()
+}
+
+fn escape_dot(label: &str) -> String {
+    let mut escaped = String::with_capacity(label.len());
+    for ch in label.chars() {
+        match ch {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            ch => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::BufReader;
+
+    use super::{ExportFormat, Subgraph};
+    use crate::transducer::{Transducer, Transition};
+    use crate::{FiniteStateTokenizer, TokenizerError, TransducerBuilder};
+
+    fn tokenizer() -> FiniteStateTokenizer {
+        let mut builder = TransducerBuilder::new();
+        let start = builder.start_state();
+        let a = builder.add_state();
+        builder
+            .add_unknown_transition(start, "\u{2}", start)
+            .unwrap()
+            .add_transition(start, 'a', "a", a)
+            .unwrap()
+            .add_transition(a, '.', " .\n", start)
+            .unwrap()
+            .set_final(start, "")
+            .unwrap()
+            .set_final(a, "\n")
+            .unwrap();
+        builder.build()
+    }
+
+    fn export(
+        tokenizer: &FiniteStateTokenizer,
+        format: ExportFormat,
+        subgraph: Subgraph,
+    ) -> Result<String, TokenizerError> {
+        let mut data = Vec::new();
+        tokenizer.export(&mut data, format, subgraph)?;
+        Ok(String::from_utf8(data).unwrap())
+    }
+
+    #[test]
+    fn export_att() {
+        assert_eq!(
+            export(&tokenizer(), ExportFormat::Att, Subgraph::default()).unwrap(),
+            "2\t2\t<unk>\t<unk>\n\
+             2\t4\ta\ta\n\
+             2\n\
+             4\t2\t.\t<space>.<newline>\n\
+             4\t5\t<eps>\t<newline>\n\
+             5\n"
+        );
+    }
+
+    #[test]
+    fn export_dot() {
+        assert_eq!(
+            export(&tokenizer(), ExportFormat::Dot, Subgraph::default()).unwrap(),
+            "digraph transducer {
+  rankdir=LR;
+  node [shape=circle];
+  start [shape=point];
+  start -> 2;
+  2 [shape=doublecircle, style=solid, label=\"2\"];
+  4 [shape=doublecircle, style=solid, label=\"4\\n/<newline>\"];
+  2 -> 2 [label=\"<unk>:<unk>\"];
+  2 -> 4 [label=\"a:a\"];
+  4 -> 2 [label=\".:<space>.<newline>\"];
+}
+"
+        );
+    }
+
+    #[test]
+    fn export_limits_depth() {
+        let subgraph = Subgraph {
+            state: Some(4),
+            depth: Some(1),
+        };
+
+        assert_eq!(
+            export(&tokenizer(), ExportFormat::Att, subgraph).unwrap(),
+            "4\t2\t.\t<space>.<newline>\n\
+             4\t5\t<eps>\t<newline>\n\
+             2\n\
+             5\n"
+        );
+
+        let read = BufReader::new(File::open("testdata/toy.proto").unwrap());
+        let toy = FiniteStateTokenizer::from_buf_read(read).unwrap();
+        let subgraph = Subgraph {
+            state: None,
+            depth: Some(0),
+        };
+        assert_eq!(export(&toy, ExportFormat::Att, subgraph).unwrap(), "2\n");
+    }
+
+    #[test]
+    fn export_rejects_unknown_state() {
+        let subgraph = Subgraph {
+            state: Some(3),
+            depth: None,
+        };
+        assert!(matches!(
+            export(&tokenizer(), ExportFormat::Dot, subgraph),
+            Err(TokenizerError::NoSuchState(3))
+        ));
+    }
+
+    #[test]
+    fn export_rejects_conflicting_final_outputs() {
+        // Both transitions of state 2 lead to state 4, but only one of
+        // them makes it final.
+        let transducer = Transducer::from_transitions(vec![
+            Transition::new(0, true, 0, ""),
+            Transition::new(0, false, 2, ""),
+            Transition::new('a' as u32, false, 4, "a"),
+            Transition::new('b' as u32, true, 4, "b").with_final_output(""),
+            Transition::new('.' as u32, true, 2, "."),
+        ]);
+
+        let mut data = Vec::new();
+        assert!(matches!(
+            super::export(
+                &transducer,
+                &mut data,
+                ExportFormat::Att,
+                Subgraph::default()
+            ),
+            Err(TokenizerError::ConflictingFinalOutput(4))
+        ));
+    }
+}
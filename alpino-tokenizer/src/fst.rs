@@ -1,68 +1,62 @@
+use std::borrow::Cow;
 use std::collections::{HashSet, VecDeque};
-use std::io::BufRead;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::Path;
 
-use prost_derive::Message;
+use memmap2::Mmap;
+use static_assertions::assert_impl_all;
 
-use crate::small_string::SmallString;
+use crate::compact::{write_compact, CompactData, CompactTransducer, MAGIC};
+use crate::export::{export, ExportFormat, Subgraph};
+use crate::import::{read_att, read_fsa_prolog};
+
+use crate::stream::{ReadChars, SentenceStream};
+use crate::tokenized::TokenizedText;
 use crate::tokenizer::Tokenizer;
+use crate::transducer::{write_proto, Transducer, Transition, TransitionProto};
 use crate::util::str_to_tokens;
-use crate::TokenizerError;
-
-struct Transducer {
-    pub transitions: Vec<Transition>,
-}
+use crate::validate::validate;
+use crate::{TokenizerError, ValidationError};
 
-/// Protobuf transition.
+/// Set of symbols that are used in a transducer.
 ///
-/// This data type should only be used during deserialization. Actual
-/// transitions are better represented using `Transition`, which performs
-/// the small string optimization.
-#[derive(Clone, PartialEq, Message)]
-struct TransitionProto {
-    #[prost(uint32, tag = "1")]
-    pub symbol: u32,
-
-    #[prost(bool, tag = "2")]
-    pub is_last_of_state: bool,
-
-    #[prost(bool, tag = "3")]
-    pub is_final_state: bool,
-
-    #[prost(uint32, tag = "4")]
-    pub next: u32,
-
-    #[prost(string, tag = "5")]
-    pub output: String,
-
-    #[prost(string, tag = "6")]
-    pub final_output: String,
+/// ASCII symbols are stored in a bit set, so that the common case does
+/// not require hashing.
+struct KnownSymbols {
+    ascii: u128,
+    other: HashSet<u32>,
 }
 
-/// Transition of a finite state transducer.
-struct Transition {
-    pub symbol: u32,
-
-    pub is_last_of_state: bool,
-
-    pub _is_final_state: bool,
-
-    pub next: u32,
-
-    pub output: SmallString,
-
-    pub final_output: SmallString,
+impl KnownSymbols {
+    fn contains(&self, symbol: u32) -> bool {
+        if symbol < 128 {
+            self.ascii & (1 << symbol) != 0
+        } else {
+            self.other.contains(&symbol)
+        }
+    }
 }
 
-impl From<TransitionProto> for Transition {
-    fn from(trans: TransitionProto) -> Self {
-        Self {
-            symbol: trans.symbol,
-            is_last_of_state: trans.is_last_of_state,
-            _is_final_state: trans.is_final_state,
-            next: trans.next,
-            output: trans.output.into(),
-            final_output: trans.final_output.into(),
+impl FromIterator<u32> for KnownSymbols {
+    fn from_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = u32>,
+    {
+        let mut known_symbols = KnownSymbols {
+            ascii: 0,
+            other: HashSet::new(),
+        };
+
+        for symbol in iter {
+            if symbol < 128 {
+                known_symbols.ascii |= 1 << symbol;
+            } else {
+                known_symbols.other.insert(symbol);
+            }
         }
+
+        known_symbols
     }
 }
 
@@ -76,10 +70,13 @@ impl From<TransitionProto> for Transition {
 /// to use the `AlpinoTokenizer` data type. `AlpinoTokenizer` applies pre- and
 /// post-processing steps that are expected by Alpino's transducer.
 pub struct FiniteStateTokenizer {
-    known_symbols: HashSet<u32>,
+    known_symbols: KnownSymbols,
     transducer: Transducer,
 }
 
+// The tokenizer is shared between threads by `tokenize_batch`.
+assert_impl_all!(FiniteStateTokenizer: Send, Sync);
+
 impl FiniteStateTokenizer {
     pub fn from_buf_read<R>(mut read: R) -> Result<Self, TokenizerError>
     where
@@ -96,75 +93,308 @@ impl FiniteStateTokenizer {
             transitions.push(transition.into());
         }
 
-        let known_symbols = transitions.iter().map(|t| t.symbol).collect();
+        Ok(Self::new(Transducer::from_transitions(transitions)))
+    }
+
+    /// Construct a tokenizer from a transducer in AT&T text format.
+    ///
+    /// The transducer must be deterministic on its input. Transitions on
+    /// `<unk>` handle characters that are not in the alphabet of the
+    /// transducer, `<unk>` in an output is replaced by the unknown
+    /// character. Transitions on `<other>` handle unknown characters
+    /// without outputting them. Final outputs can be represented as
+    /// transitions on `<eps>` to a final state without transitions.
+    pub fn from_att<R>(read: R) -> Result<Self, TokenizerError>
+    where
+        R: BufRead,
+    {
+        Ok(Self::new(read_att(read)?))
+    }
+
+    /// Construct a tokenizer from a transducer in FSA utilities Prolog format.
+    ///
+    /// Unknown characters are matched using `not_in` or `true` inputs. For
+    /// example, `$@(not_in([a,b]))` copies unknown characters to the
+    /// output. As with [`FiniteStateTokenizer::from_att`], final outputs
+    /// can be represented as transitions on the empty input (`[]`) to a
+    /// final state without transitions.
+    pub fn from_fsa_prolog<R>(read: R) -> Result<Self, TokenizerError>
+    where
+        R: BufRead,
+    {
+        Ok(Self::new(read_fsa_prolog(read)?))
+    }
+
+    /// Construct a tokenizer from a transducer in the compact format.
+    ///
+    /// The transitions and outputs are used directly from `data`, without
+    /// copying or decoding them.
+    pub fn from_compact(data: impl Into<Cow<'static, [u8]>>) -> Result<Self, TokenizerError> {
+        let transducer = CompactTransducer::new(CompactData::Bytes(data.into()))?;
+        Ok(Self::new(Transducer::Compact(transducer)))
+    }
 
-        Ok(FiniteStateTokenizer {
+    /// Memory-map a transducer in the compact format.
+    ///
+    /// Memory-mapped transducers are shared between processes that use
+    /// the same transducer file. The file must not be modified while the
+    /// tokenizer is in use.
+    pub fn from_compact_file(path: impl AsRef<Path>) -> Result<Self, TokenizerError> {
+        let file = File::open(path)?;
+
+        // Safety: the mapping is read-only, modification of the
+        // underlying file is documented as unsupported.
+        let mmap = unsafe { Mmap::map(&file)? };
+
+        let transducer = CompactTransducer::new(CompactData::Mmap(mmap))?;
+        Ok(Self::new(Transducer::Compact(transducer)))
+    }
+
+    /// Load a tokenizer from a file.
+    ///
+    /// Transducers in the compact format are memory-mapped, other files
+    /// are read as length-delimited protobuf.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, TokenizerError> {
+        let path = path.as_ref();
+
+        let mut magic = Vec::with_capacity(MAGIC.len());
+        File::open(path)?
+            .take(MAGIC.len() as u64)
+            .read_to_end(&mut magic)?;
+
+        if magic == MAGIC {
+            Self::from_compact_file(path)
+        } else {
+            Self::from_buf_read(BufReader::new(File::open(path)?))
+        }
+    }
+
+    /// Write the transducer in the compact format.
+    pub fn write_compact<W>(&self, write: W) -> Result<(), TokenizerError>
+    where
+        W: Write,
+    {
+        write_compact(&self.transducer, write)
+    }
+
+    /// Write the transducer in the protobuf format.
+    ///
+    /// The transducer is written as length-delimited `TransitionProto`
+    /// records, which can be read with
+    /// [`FiniteStateTokenizer::from_buf_read`].
+    pub fn write_proto<W>(&self, write: W) -> Result<(), TokenizerError>
+    where
+        W: Write,
+    {
+        write_proto(&self.transducer, write)
+    }
+
+    /// Export the transducer in a text format.
+    ///
+    /// This writes the states and transitions of `subgraph`, which is
+    /// mainly useful for inspecting or debugging a transducer.
+    pub fn export<W>(
+        &self,
+        write: W,
+        format: ExportFormat,
+        subgraph: Subgraph,
+    ) -> Result<(), TokenizerError>
+    where
+        W: Write,
+    {
+        export(&self.transducer, write, format, subgraph)
+    }
+
+    /// Validate the transducer.
+    ///
+    /// The tokenizer may panic when a malformed transducer is used. This
+    /// method checks that the transducer is well-formed, see
+    /// [`ValidationError`] for the checks that are performed. Validation
+    /// follows all paths through the transducer, so it is not done
+    /// automatically when a transducer is loaded.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        validate(&self.transducer)
+    }
+
+    /// Validate the transducer, returning the tokenizer if it is well-formed.
+    ///
+    /// This method can be chained with the constructors to validate a
+    /// transducer on load:
+    ///
+    /// ```no_run
+    /// # use alpino_tokenizer::{FiniteStateTokenizer, TokenizerError};
+    /// # fn main() -> Result<(), TokenizerError> {
+    /// let tokenizer = FiniteStateTokenizer::from_file("tokenizer.proto")?.validated()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn validated(self) -> Result<Self, ValidationError> {
+        self.validate()?;
+        Ok(self)
+    }
+
+    #[cfg(test)]
+    pub(crate) fn transducer(&self) -> &Transducer {
+        &self.transducer
+    }
+
+    pub(crate) fn new(transducer: Transducer) -> Self {
+        // Symbols 1 and 2 are used for transitions on unknown characters,
+        // the corresponding characters U+0001 and U+0002 should also be
+        // treated as unknown. Otherwise, U+0002 would take the transition
+        // for unknown characters without being queued, and its output
+        // would read from an empty unknown character queue.
+        let known_symbols = transducer
+            .alphabet()
+            .into_iter()
+            .filter(|&symbol| symbol != 1 && symbol != 2)
+            .collect();
+
+        FiniteStateTokenizer {
             known_symbols,
-            transducer: Transducer { transitions },
-        })
+            transducer,
+        }
     }
 
-    pub(crate) fn tokenize_raw<I>(&self, chars: I) -> Option<String>
+    pub(crate) fn tokenize_raw<I>(&self, chars: I) -> Result<String, TokenizerError>
     where
         I: IntoIterator<Item = char>,
     {
         let mut output = String::new();
+        Walker::new(self).tokenize_into(chars, &mut output)?;
+        Ok(output)
+    }
+
+    /// Tokenize a text, finding transitions by scanning linearly.
+    ///
+    /// This method only exists to provide a baseline for the transition
+    /// index in the benchmarks. Use `Tokenizer::tokenize_text` instead.
+    #[doc(hidden)]
+    pub fn tokenize_linear_scan(&self, text: &str) -> Result<TokenizedText, TokenizerError> {
+        let mut output = String::new();
+        Walker::new(self).tokenize_into_with(
+            text.chars(),
+            &mut output,
+            Transducer::find_transition_linear,
+        )?;
+        Ok(TokenizedText::new(output))
+    }
+
+    /// Sentence-split and tokenize a stream of characters.
+    ///
+    /// This method returns an iterator over the sentences in the stream.
+    /// Each sentence is returned as soon as the transducer has decided on
+    /// the sentence boundary, so that long paragraphs do not have to be
+    /// buffered in memory. The iterator ends after the first error.
+    ///
+    /// Streaming only applies the transducer. The pre- and postprocessing
+    /// of `AlpinoTokenizer` rewrite whole paragraphs, for instance to mark
+    /// enumerations that span several sentences, and are not applied.
+    /// Use `AlpinoTokenizer::tokenize` on paragraphs when the processing is
+    /// required.
+    pub fn tokenize_chars<'a, I>(
+        &'a self,
+        chars: I,
+    ) -> impl Iterator<Item = Result<Vec<String>, TokenizerError>> + 'a
+    where
+        I: IntoIterator<Item = char>,
+        I::IntoIter: 'a,
+    {
+        SentenceStream::new(self, chars.into_iter().map(Ok))
+    }
+
+    /// Sentence-split and tokenize UTF-8 text from a reader.
+    ///
+    /// This method is similar to `tokenize_chars`, but reads the text
+    /// incrementally from `read`.
+    pub fn tokenize_buf_read<'a, R>(
+        &'a self,
+        read: R,
+    ) -> impl Iterator<Item = Result<Vec<String>, TokenizerError>> + 'a
+    where
+        R: BufRead + 'a,
+    {
+        SentenceStream::new(self, ReadChars::new(read))
+    }
+
+    /// Sentence-split and tokenize a paragraph, recovering from errors.
+    ///
+    /// This method is similar to `tokenize`, but does not fail when the
+    /// transducer does not accept a character. Instead, the output for the
+    /// text before the character is finalized, the character and subsequent
+    /// non-whitespace characters are added as a single token, and the
+    /// transducer is restarted at the next whitespace.
+    ///
+    /// Returns the sentences and the locations where recovery happened.
+    pub fn tokenize_with_recovery(&self, text: &str) -> (Vec<Vec<String>>, Vec<Recovery>) {
+        let (tokenized, recoveries) = self.tokenize_raw_with_recovery(text.chars());
+        (str_to_tokens(&tokenized), recoveries)
+    }
+
+    pub(crate) fn tokenize_raw_with_recovery<I>(&self, chars: I) -> (String, Vec<Recovery>)
+    where
+        I: IntoIterator<Item = char>,
+    {
+        let mut output = String::new();
+        let mut recoveries = Vec::new();
+
+        // Offset in the output where a separator should be inserted if
+        // the transducer produces output after recovery.
+        let mut separator_at = None;
+
+        let mut walker = Walker::new(self);
+        let mut chars = chars.into_iter().enumerate().peekable();
+        while let Some((offset, ch)) = chars.next() {
+            let state = match walker.step(ch, &mut output) {
+                Ok(()) => {
+                    insert_separator(&mut output, &mut separator_at);
+                    continue;
+                }
+                Err(state) => state,
+            };
+
+            // Finalize the output for the text before the rejected character.
+            walker.finish(&mut output);
+            walker = Walker::new(self);
+
+            let mut len = 1;
+            if !ch.is_whitespace() {
+                if !output.is_empty() && !output.ends_with(char::is_whitespace) {
+                    output.push(' ');
+                }
 
-        let mut unknown_queue = VecDeque::new();
-        let mut trans_offset = 1;
-        let mut transition = &self.transducer.transitions[trans_offset];
-        for ch in chars {
-            trans_offset = transition.next as usize;
-            transition = &self.transducer.transitions[trans_offset];
-            let symbol = ch as u32;
-
-            if transition.symbol != 1 || self.known_symbols.contains(&symbol) {
-                // If the character is unknown and we are in a transition that handles
-                // unknown characters, we are done. Otherwise, find a transition matching
-                // the character.
-                if transition.symbol == 2 && !self.known_symbols.contains(&symbol) {
-                    unknown_queue.push_back(ch);
-                } else {
-                    // Linearly scan the transitions until we have found one that matches
-                    // the character.
-                    while !transition.is_last_of_state && symbol > transition.symbol {
-                        trans_offset += 1;
-                        transition = &self.transducer.transitions[trans_offset];
-                    }
-
-                    // If the current transition is not a match, the string is not in the
-                    // language of the transducer.
-                    if transition.symbol != symbol {
-                        return None;
-                    }
+                // Add the rejected character and the characters up to the
+                // next whitespace as a single token.
+                output.push(ch);
+                while let Some((_, ch)) = chars.next_if(|(_, ch)| !ch.is_whitespace()) {
+                    output.push(ch);
+                    len += 1;
                 }
+
+                separator_at = Some(output.len());
             }
 
-            // Append transition output, replacing unknown characters from
-            // the unknown character queue.
-            output.extend(Self::replace_output_with_queue(
-                &transition.output,
-                &mut unknown_queue,
-            ));
+            recoveries.push(Recovery {
+                offset,
+                len,
+                character: ch,
+                state,
+            });
         }
 
-        // Append final output, replacing unknown characters from the unknown
-        // character queue.
-        output.extend(Self::replace_output_with_queue(
-            &transition.final_output,
-            &mut unknown_queue,
-        ));
+        walker.finish(&mut output);
+        insert_separator(&mut output, &mut separator_at);
 
-        Some(output)
+        (output, recoveries)
     }
+
     fn replace_output_with_queue<'a>(
         output: &'a str,
         unknown_queue: &'a mut VecDeque<char>,
     ) -> impl Iterator<Item = char> + 'a {
         // We panic on an empty queue, since this implies an incorrect
-        // automaton. Perhaps we could validate these when reading the
-        // transducer? Seems prohibitively expensive, since we would
-        // have to check every possible path?
+        // automaton. Transducers that pass validation never underflow
+        // the queue.
         output.chars().map(move |c| {
             if c == char::from(2) {
                 unknown_queue
@@ -177,9 +407,299 @@ impl FiniteStateTokenizer {
     }
 }
 
+// Insert a separator at the given output offset, if the transducer
+// produced non-whitespace output after the offset.
+fn insert_separator(output: &mut String, separator_at: &mut Option<usize>) {
+    if let Some(offset) = *separator_at {
+        if output.len() > offset {
+            if !output[offset..].starts_with(char::is_whitespace) {
+                output.insert(offset, ' ');
+            }
+            *separator_at = None;
+        }
+    }
+}
+
+/// Location where the tokenizer recovered from an error.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Recovery {
+    /// Character offset of the character that was not accepted.
+    pub offset: usize,
+
+    /// Length of the rejected region in characters.
+    ///
+    /// The rejected region starts at the rejected character and ends before
+    /// the next whitespace.
+    pub len: usize,
+
+    /// The character that was not accepted by the transducer.
+    pub character: char,
+
+    /// The transducer state in which no transition was found.
+    pub state: usize,
+}
+
+/// Walker over transducer states.
+pub(crate) struct Walker<'a> {
+    tokenizer: &'a FiniteStateTokenizer,
+    transition: usize,
+    unknown_queue: VecDeque<char>,
+}
+
+impl<'a> Walker<'a> {
+    pub(crate) fn new(tokenizer: &'a FiniteStateTokenizer) -> Self {
+        Walker {
+            tokenizer,
+            transition: 1,
+            unknown_queue: VecDeque::new(),
+        }
+    }
+
+    /// Return to the start state and clear the unknown character queue.
+    pub(crate) fn reset(&mut self) {
+        self.transition = 1;
+        self.unknown_queue.clear();
+    }
+
+    /// Tokenize `chars`, appending the transducer output to `output`.
+    ///
+    /// The walker should be in the start state.
+    pub(crate) fn tokenize_into<I>(
+        &mut self,
+        chars: I,
+        output: &mut String,
+    ) -> Result<(), TokenizerError>
+    where
+        I: IntoIterator<Item = char>,
+    {
+        self.tokenize_into_with(chars, output, Transducer::find_transition)
+    }
+
+    /// Tokenize `chars`, using `find` to look up transitions.
+    fn tokenize_into_with<I, F>(
+        &mut self,
+        chars: I,
+        output: &mut String,
+        find: F,
+    ) -> Result<(), TokenizerError>
+    where
+        I: IntoIterator<Item = char>,
+        F: Fn(&Transducer, usize, u32) -> Option<usize>,
+    {
+        for (offset, ch) in chars.into_iter().enumerate() {
+            if let Err(state) = self.step_with(ch, output, &find) {
+                return Err(TokenizerError::NoTransition {
+                    offset,
+                    character: ch,
+                    state,
+                    partial_output: output.clone(),
+                });
+            }
+        }
+
+        self.finish(output);
+
+        Ok(())
+    }
+
+    /// Take the transition for `ch`, appending its output to `output`.
+    ///
+    /// Returns the current state as an error if there is no transition
+    /// for the character.
+    pub(crate) fn step(&mut self, ch: char, output: &mut String) -> Result<(), usize> {
+        self.step_with(ch, output, Transducer::find_transition)
+    }
+
+    fn step_with<F>(&mut self, ch: char, output: &mut String, find: F) -> Result<(), usize>
+    where
+        F: Fn(&Transducer, usize, u32) -> Option<usize>,
+    {
+        let transducer = &self.tokenizer.transducer;
+        let known_symbols = &self.tokenizer.known_symbols;
+
+        let state = transducer.next(self.transition) as usize;
+        let mut transition = state;
+        let first_symbol = transducer.symbol(state);
+        let symbol = ch as u32;
+
+        let known = known_symbols.contains(symbol);
+
+        if first_symbol != 1 || known {
+            // If the character is unknown and we are in a transition that handles
+            // unknown characters, we are done. Otherwise, find a transition matching
+            // the character.
+            if first_symbol == 2 && !known {
+                self.unknown_queue.push_back(ch);
+            } else {
+                // If there is no matching transition, the string is not in the
+                // language of the transducer.
+                transition = find(transducer, state, symbol).ok_or(state)?;
+            }
+        }
+
+        // Append transition output, replacing unknown characters from
+        // the unknown character queue.
+        output.extend(FiniteStateTokenizer::replace_output_with_queue(
+            transducer.output(transition),
+            &mut self.unknown_queue,
+        ));
+
+        self.transition = transition;
+
+        Ok(())
+    }
+
+    /// Append the final output to `output`.
+    pub(crate) fn finish(&mut self, output: &mut String) {
+        // Append final output, replacing unknown characters from the unknown
+        // character queue.
+        output.extend(FiniteStateTokenizer::replace_output_with_queue(
+            self.tokenizer.transducer.final_output(self.transition),
+            &mut self.unknown_queue,
+        ));
+    }
+}
+
 impl Tokenizer for FiniteStateTokenizer {
-    fn tokenize(&self, text: &str) -> Option<Vec<Vec<String>>> {
+    fn tokenize_text(&self, text: &str) -> Result<TokenizedText, TokenizerError> {
         let tokenized = self.tokenize_raw(text.chars())?;
-        Some(str_to_tokens(&tokenized))
+        Ok(TokenizedText::new(tokenized))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::fs::{self, File};
+    use std::io::BufReader;
+
+    use super::{FiniteStateTokenizer, Recovery};
+    use crate::transducer::Transducer;
+    use crate::util::str_to_tokens;
+    use crate::{Tokenizer, TokenizerError};
+
+    fn tokenizer() -> FiniteStateTokenizer {
+        let read = BufReader::new(File::open("testdata/toy.proto").unwrap());
+        FiniteStateTokenizer::from_buf_read(read).unwrap()
+    }
+
+    #[test]
+    fn reserved_symbols_are_unknown_characters() {
+        let tokenizer = tokenizer();
+
+        // U+0001 and U+0002 are tokenized like other unknown characters.
+        for ch in ['\u{1}', '\u{2}'] {
+            let text = format!("Dit is a{}b en {} een zin.", ch, ch);
+            assert_eq!(
+                tokenizer.tokenize(&text).unwrap(),
+                tokenizer
+                    .tokenize("Dit is a☃b en ☃ een zin.")
+                    .unwrap()
+                    .into_iter()
+                    .map(|sentence| sentence
+                        .into_iter()
+                        .map(|token| token.replace('☃', &ch.to_string()))
+                        .collect::<Vec<_>>())
+                    .collect::<Vec<_>>()
+            );
+        }
+    }
+
+    #[test]
+    fn no_transition_error() {
+        let tokenizer = tokenizer();
+        match tokenizer.tokenize("Dit is\0een zin.") {
+            Err(TokenizerError::NoTransition {
+                offset,
+                character,
+                partial_output,
+                ..
+            }) => {
+                assert_eq!(offset, 6);
+                assert_eq!(character, '\0');
+                assert_eq!(partial_output, "Dit is");
+            }
+            result => panic!("Expected transition error, got: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn recovers_from_rejected_characters() {
+        let tokenizer = tokenizer();
+
+        let (tokenized, recoveries) =
+            tokenizer.tokenize_with_recovery("Dit is\0een zin. Nog\0\0 een zin.");
+        assert_eq!(
+            tokenized,
+            str_to_tokens("Dit is \0een zin .\nNog \0\0 een zin .")
+        );
+        assert_eq!(
+            recoveries
+                .iter()
+                .map(|r| (r.offset, r.len, r.character))
+                .collect::<Vec<_>>(),
+            vec![(6, 4, '\0'), (19, 2, '\0')]
+        );
+    }
+
+    #[test]
+    fn recovery_is_not_needed_for_accepted_text() {
+        let tokenizer = tokenizer();
+        let text = "Dit is een zin. En dit is nog een zin...";
+        let (tokenized, recoveries) = tokenizer.tokenize_with_recovery(text);
+        assert_eq!(tokenized, tokenizer.tokenize(text).unwrap());
+        assert_eq!(recoveries, Vec::<Recovery>::new());
+    }
+
+    #[test]
+    fn index_finds_same_transitions_as_linear_scan() {
+        let tokenizer = tokenizer();
+        let (transitions, index) = match &tokenizer.transducer {
+            Transducer::Owned { transitions, index } => (transitions, index),
+            Transducer::Compact(_) => unreachable!(),
+        };
+
+        let states = transitions
+            .iter()
+            .map(|t| t.next as usize)
+            .collect::<HashSet<_>>();
+
+        for state in states {
+            for symbol in (0..300).chain([8203, 65279, 70000]) {
+                let linear = tokenizer.transducer.find_transition_linear(state, symbol);
+
+                assert_eq!(index.find(transitions, state, symbol), linear);
+            }
+        }
+    }
+
+    #[test]
+    fn write_proto_round_trips() {
+        let data = fs::read("testdata/toy.proto").unwrap();
+
+        let mut written = Vec::new();
+        tokenizer().write_proto(&mut written).unwrap();
+        assert_eq!(written, data);
+
+        let mut compact_data = Vec::new();
+        tokenizer().write_compact(&mut compact_data).unwrap();
+        let compact = FiniteStateTokenizer::from_compact(compact_data).unwrap();
+        let mut written = Vec::new();
+        compact.write_proto(&mut written).unwrap();
+        assert_eq!(written, data);
+    }
+
+    #[test]
+    fn write_proto_of_imported_transducer() {
+        let att = "0\t0\ta\ta\n0\t0\t<space>\t<newline>\n0\n";
+        let tokenizer = FiniteStateTokenizer::from_att(att.as_bytes()).unwrap();
+
+        let mut data = Vec::new();
+        tokenizer.write_proto(&mut data).unwrap();
+        let read = FiniteStateTokenizer::from_buf_read(&*data).unwrap();
+        assert_eq!(
+            read.tokenize("aa a").unwrap(),
+            vec![vec!["aa".to_string()], vec!["a".to_string()]]
+        );
     }
 }
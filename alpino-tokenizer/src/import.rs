@@ -0,0 +1,851 @@
+use std::collections::{BTreeSet, HashMap};
+use std::io::BufRead;
+use std::iter::Peekable;
+use std::str::Chars;
+
+use crate::state_graph::{BuildError, StateGraph};
+use crate::transducer::Transducer;
+use crate::TokenizerError;
+
+/// Input of a transition in an imported transducer.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Input {
+    Epsilon,
+    Symbol(u32),
+}
+
+/// Input of a transition label in the Prolog format.
+#[derive(Debug)]
+enum PrologInput {
+    Epsilon,
+
+    /// Any of the characters.
+    In(Vec<u32>),
+
+    /// Any character that is not one of the characters, including
+    /// unknown characters.
+    NotIn(Vec<u32>),
+}
+
+impl PrologInput {
+    fn symbols(&self) -> &[u32] {
+        match self {
+            PrologInput::Epsilon => &[],
+            PrologInput::In(symbols) | PrologInput::NotIn(symbols) => symbols,
+        }
+    }
+}
+
+/// Transition of an imported transducer.
+struct Arc {
+    line: usize,
+    from: usize,
+    input: Input,
+    output: String,
+    to: usize,
+}
+
+/// State graph that is constructed from imported transitions.
+///
+/// Imported transducers can represent final outputs as transitions on
+/// the empty input to a final state without outgoing transitions. Such
+/// transitions are converted to final outputs.
+#[derive(Default)]
+struct Import {
+    graph: StateGraph,
+    state_ids: HashMap<String, usize>,
+    start: Option<usize>,
+    arcs: Vec<Arc>,
+    finals: Vec<(usize, usize, String)>,
+}
+
+impl Import {
+    fn state(&mut self, name: &str) -> usize {
+        if let Some(&state) = self.state_ids.get(name) {
+            return state;
+        }
+
+        let state = self.graph.add_state();
+        self.state_ids.insert(name.to_owned(), state);
+        state
+    }
+
+    fn into_transducer(mut self) -> Result<Transducer, TokenizerError> {
+        let start = self
+            .start
+            .ok_or_else(|| import_error(0, "transducer has no states"))?;
+
+        for (line, state, final_output) in self.finals {
+            self.graph
+                .set_final(state, final_output)
+                .map_err(|err| graph_error(line, err))?;
+        }
+
+        let (epsilons, arcs): (Vec<_>, Vec<_>) = self
+            .arcs
+            .into_iter()
+            .partition(|arc| arc.input == Input::Epsilon);
+
+        for arc in arcs {
+            if let Input::Symbol(symbol) = arc.input {
+                self.graph
+                    .add_arc(arc.from, symbol, arc.output, arc.to)
+                    .map_err(|err| graph_error(arc.line, err))?;
+            }
+        }
+
+        for arc in epsilons {
+            let final_output = match self.graph.final_output(arc.to) {
+                Some(final_output) if !self.graph.has_arcs(arc.to) => final_output,
+                _ => return Err(import_error(
+                    arc.line,
+                    "transitions on the empty input must lead to a final state without transitions",
+                )),
+            };
+
+            let final_output = format!("{}{}", arc.output, final_output);
+            self.graph
+                .set_final(arc.from, final_output)
+                .map_err(|err| graph_error(arc.line, err))?;
+        }
+
+        Ok(self.graph.into_transducer(start))
+    }
+}
+
+fn import_error(line: usize, message: impl Into<String>) -> TokenizerError {
+    TokenizerError::ImportError {
+        line,
+        message: message.into(),
+    }
+}
+
+fn graph_error(line: usize, err: BuildError) -> TokenizerError {
+    // Graph errors use internal state numbers, so only report the kind
+    // of error along with the line number.
+    let message = match err {
+        BuildError::DuplicateSymbol { .. } => "state has multiple transitions on the same symbol",
+        BuildError::ConflictingFinalOutput { .. } => "state has multiple final outputs",
+        BuildError::ConflictingUnknownSymbols { .. } => {
+            "state has transitions on both <unk> and <other>"
+        }
+        BuildError::ReservedCharacter { .. } => "U+0000 cannot be used as an input symbol",
+        BuildError::UnknownState { .. }
+        | BuildError::EmptyPair
+        | BuildError::ConflictingPair { .. }
+        | BuildError::PairsOnStartState { .. } => {
+            unreachable!("Import only adds transitions between added states")
+        }
+    };
+
+    import_error(line, message)
+}
+
+/// Read a transducer in AT&T text format.
+///
+/// Each line is either a transition with the source state, target state,
+/// input, and output, or a final state. The line can have an additional
+/// weight, which is ignored. The source state of the first line is the
+/// start state.
+///
+/// Inputs must be single characters, `<unk>` for unknown characters that
+/// are output through `<unk>` placeholders in outputs, or `<other>` for
+/// unknown characters that are not output. The labels `<eps>`, `<space>`,
+/// `<tab>`, `<newline>` and `<U+XXXX>` are used for the empty string,
+/// whitespace, and code points. The corresponding HFST symbols such as
+/// `@0@`, `@_SPACE_@` and `@_IDENTITY_SYMBOL_@` are also supported.
+pub(crate) fn read_att<R>(read: R) -> Result<Transducer, TokenizerError>
+where
+    R: BufRead,
+{
+    let mut import = Import::default();
+
+    for (idx, line) in read.lines().enumerate() {
+        let line = line?;
+        let line_no = idx + 1;
+
+        let fields = line.split_whitespace().collect::<Vec<_>>();
+        if fields.is_empty() {
+            continue;
+        }
+
+        let from = import.state(fields[0]);
+        import.start.get_or_insert(from);
+
+        match fields.len() {
+            1 | 2 => import.finals.push((line_no, from, String::new())),
+            3..=5 => {
+                let to = import.state(fields[1]);
+                let input = parse_input(fields[2]).ok_or_else(|| {
+                    import_error(line_no, format!("invalid input symbol: {}", fields[2]))
+                })?;
+
+                // Acceptor transitions have a single label.
+                let output = match fields.len() {
+                    3 => fields[2],
+                    _ => fields[3],
+                };
+
+                import.arcs.push(Arc {
+                    line: line_no,
+                    from,
+                    input,
+                    output: parse_att_label(output),
+                    to,
+                });
+            }
+            _ => return Err(import_error(line_no, "too many fields")),
+        }
+    }
+
+    import.into_transducer()
+}
+
+fn parse_input(label: &str) -> Option<Input> {
+    match label {
+        "<unk>" | "@_IDENTITY_SYMBOL_@" => Some(Input::Symbol(2)),
+        "<other>" | "@_UNKNOWN_SYMBOL_@" => Some(Input::Symbol(1)),
+        _ => {
+            let label = parse_att_label(label);
+            let mut chars = label.chars();
+            match (chars.next(), chars.next()) {
+                (None, _) => Some(Input::Epsilon),
+                (Some(ch), None) => Some(Input::Symbol(ch as u32)),
+                _ => None,
+            }
+        }
+    }
+}
+
+/// Decode an AT&T label.
+///
+/// Unknown character placeholders are decoded to U+0002.
+fn parse_att_label(label: &str) -> String {
+    let mut decoded = String::with_capacity(label.len());
+    let mut rest = label;
+
+    while let Some(ch) = rest.chars().next() {
+        if let Some((decoded_ch, len)) = parse_escape(rest) {
+            decoded.extend(decoded_ch);
+            rest = &rest[len..];
+        } else {
+            decoded.push(ch);
+            rest = &rest[ch.len_utf8()..];
+        }
+    }
+
+    decoded
+}
+
+fn parse_escape(s: &str) -> Option<(Option<char>, usize)> {
+    const ESCAPES: &[(&str, Option<char>)] = &[
+        ("<eps>", None),
+        ("<unk>", Some('\u{2}')),
+        ("<space>", Some(' ')),
+        ("<tab>", Some('\t')),
+        ("<newline>", Some('\n')),
+        ("@0@", None),
+        ("@_EPSILON_SYMBOL_@", None),
+        ("@_IDENTITY_SYMBOL_@", Some('\u{2}')),
+        ("@_SPACE_@", Some(' ')),
+        ("@_TAB_@", Some('\t')),
+    ];
+
+    for &(escape, ch) in ESCAPES {
+        if s.starts_with(escape) {
+            return Some((ch, escape.len()));
+        }
+    }
+
+    let code_point = s.strip_prefix("<U+")?;
+    let end = code_point.find('>')?;
+    let ch = u32::from_str_radix(&code_point[..end], 16)
+        .ok()
+        .and_then(char::from_u32)?;
+    Some((Some(ch), "<U+>".len() + end))
+}
+
+/// Read a transducer in the Prolog format of the FSA utilities.
+///
+/// The transducer is a term of the form
+/// `fa(Preds, NStates, Starts, Finals, Transitions, Jumps)`, where
+/// `Starts` is a list with a single start state. Transitions are of the
+/// form `trans(From, Label, To)` and jumps of the form `jump(From, To)`.
+/// Final states can also be given as `State/Output` to add a final output.
+///
+/// Labels are of the form `In/Out` or `$@(In)` for identities, inputs are
+/// single characters, `[]` for the empty input, `in(Chars)`,
+/// `not_in(Chars)`, or `true`. The last two are expanded to the
+/// characters of the alphabet, the characters that are used in labels,
+/// that they match, plus a transition for unknown characters. The output
+/// of an identity on unknown characters is the unknown character.
+pub(crate) fn read_fsa_prolog<R>(mut read: R) -> Result<Transducer, TokenizerError>
+where
+    R: BufRead,
+{
+    let mut data = String::new();
+    read.read_to_string(&mut data)?;
+
+    let mut parser = TermParser::new(&data);
+    let term = parser.parse_term()?;
+    parser.skip_whitespace();
+    if parser.peek_char() == Some('.') {
+        parser.next_char();
+    }
+    parser.skip_whitespace();
+    if parser.peek_char().is_some() {
+        return Err(import_error(parser.line, "unexpected data after term"));
+    }
+
+    let args = match term.term {
+        TermKind::Compound(ref name, ref args) if name == "fa" && args.len() == 6 => args,
+        _ => return Err(import_error(term.line, "expected fa/6 term")),
+    };
+
+    let mut import = Import::default();
+
+    match args[2].as_list() {
+        Some([start]) => {
+            let start = import.prolog_state(start)?;
+            import.start = Some(start);
+        }
+        _ => {
+            return Err(import_error(
+                args[2].line,
+                "expected a list with a single start state",
+            ))
+        }
+    }
+
+    for state in list(&args[3])? {
+        match state.as_binary("/") {
+            Some((state, output)) => {
+                let line = state.line;
+                let state = import.prolog_state(state)?;
+                import.finals.push((line, state, prolog_output(output)?));
+            }
+            None => {
+                let line = state.line;
+                let state = import.prolog_state(state)?;
+                import.finals.push((line, state, String::new()));
+            }
+        }
+    }
+
+    let mut transitions = Vec::new();
+    for trans in list(&args[4])? {
+        let (from, label, to) = match &trans.term {
+            TermKind::Compound(name, args) if name == "trans" && args.len() == 3 => {
+                (&args[0], &args[1], &args[2])
+            }
+            _ => return Err(import_error(trans.line, "expected trans/3 term")),
+        };
+
+        let from = import.prolog_state(from)?;
+        let to = import.prolog_state(to)?;
+        transitions.push((trans.line, from, prolog_label(label)?, to));
+    }
+
+    // The alphabet is needed to expand labels that match any character
+    // except some characters.
+    let alphabet = transitions
+        .iter()
+        .flat_map(|(_, _, (input, _), _)| input.symbols())
+        .copied()
+        .collect::<BTreeSet<_>>();
+
+    for (line, from, (input, output), to) in transitions {
+        for (input, output) in expand_label(&input, output.as_deref(), &alphabet) {
+            import.arcs.push(Arc {
+                line,
+                from,
+                input,
+                output,
+                to,
+            });
+        }
+    }
+
+    for jump in list(&args[5])? {
+        let (from, to) = match &jump.term {
+            TermKind::Compound(name, args) if name == "jump" && args.len() == 2 => {
+                (&args[0], &args[1])
+            }
+            _ => return Err(import_error(jump.line, "expected jump/2 term")),
+        };
+
+        let from = import.prolog_state(from)?;
+        let to = import.prolog_state(to)?;
+        import.arcs.push(Arc {
+            line: jump.line,
+            from,
+            input: Input::Epsilon,
+            output: String::new(),
+            to,
+        });
+    }
+
+    import.into_transducer()
+}
+
+impl Import {
+    fn prolog_state(&mut self, term: &Term) -> Result<usize, TokenizerError> {
+        match &term.term {
+            TermKind::Int(state) => Ok(self.state(state)),
+            _ => Err(import_error(term.line, "expected a state number")),
+        }
+    }
+}
+
+fn list(term: &Term) -> Result<&[Term], TokenizerError> {
+    term.as_list()
+        .ok_or_else(|| import_error(term.line, "expected a list"))
+}
+
+/// Decode a transition label to its input and output.
+///
+/// The output is `None` for identities.
+fn prolog_label(label: &Term) -> Result<(PrologInput, Option<String>), TokenizerError> {
+    if let Some((input, output)) = label.as_binary("/") {
+        return Ok((prolog_input(input)?, Some(prolog_output(output)?)));
+    }
+
+    let input = match &label.term {
+        TermKind::Compound(name, args) if name == "$@" && args.len() == 1 => &args[0],
+        _ => label,
+    };
+
+    Ok((prolog_input(input)?, None))
+}
+
+/// Expand a transition label to inputs and outputs.
+fn expand_label(
+    input: &PrologInput,
+    output: Option<&str>,
+    alphabet: &BTreeSet<u32>,
+) -> Vec<(Input, String)> {
+    let inputs = match input {
+        PrologInput::Epsilon => vec![Input::Epsilon],
+        PrologInput::In(symbols) => symbols.iter().copied().map(Input::Symbol).collect(),
+        PrologInput::NotIn(excluded) => alphabet
+            .iter()
+            .copied()
+            .filter(|symbol| !excluded.contains(symbol))
+            .chain(std::iter::once(1))
+            .map(Input::Symbol)
+            .collect(),
+    };
+
+    inputs
+        .into_iter()
+        .map(|input| match output {
+            Some(output) => (input, output.to_owned()),
+            None => identity(input),
+        })
+        .collect()
+}
+
+/// Input and output of an identity on the input.
+fn identity(input: Input) -> (Input, String) {
+    let output = match input {
+        Input::Epsilon => String::new(),
+        Input::Symbol(1) | Input::Symbol(2) => '\u{2}'.to_string(),
+        Input::Symbol(symbol) => char::from_u32(symbol)
+            .expect("Input symbol is not a character")
+            .to_string(),
+    };
+
+    // Unknown characters can only be output when they are queued.
+    let input = match input {
+        Input::Symbol(1) => Input::Symbol(2),
+        input => input,
+    };
+
+    (input, output)
+}
+
+fn prolog_input(term: &Term) -> Result<PrologInput, TokenizerError> {
+    match &term.term {
+        TermKind::List(items) if items.is_empty() => Ok(PrologInput::Epsilon),
+        TermKind::Atom(name) if name == "true" => Ok(PrologInput::NotIn(Vec::new())),
+        TermKind::Compound(name, args) if name == "not_in" && args.len() == 1 => {
+            Ok(PrologInput::NotIn(prolog_chars(&args[0])?))
+        }
+        TermKind::Compound(name, args) if name == "in" && args.len() == 1 => {
+            Ok(PrologInput::In(prolog_chars(&args[0])?))
+        }
+        _ => Ok(PrologInput::In(vec![prolog_char(term)?])),
+    }
+}
+
+fn prolog_chars(term: &Term) -> Result<Vec<u32>, TokenizerError> {
+    list(term)?.iter().map(prolog_char).collect()
+}
+
+fn prolog_char(term: &Term) -> Result<u32, TokenizerError> {
+    match &term.term {
+        TermKind::Atom(name) | TermKind::Int(name) => {
+            let mut chars = name.chars();
+            match (chars.next(), chars.next()) {
+                (Some(ch), None) => Ok(ch as u32),
+                _ => Err(import_error(
+                    term.line,
+                    format!("input is not a single character: {}", name),
+                )),
+            }
+        }
+        _ => Err(import_error(term.line, "unsupported input")),
+    }
+}
+
+fn prolog_output(term: &Term) -> Result<String, TokenizerError> {
+    match &term.term {
+        TermKind::Atom(name) | TermKind::Int(name) => Ok(name.clone()),
+        TermKind::List(items) => {
+            let mut output = String::new();
+            for item in items {
+                output.push_str(&prolog_output(item)?);
+            }
+            Ok(output)
+        }
+        _ => Err(import_error(term.line, "unsupported output")),
+    }
+}
+
+/// Prolog term with the line on which it starts.
+#[derive(Debug)]
+struct Term {
+    line: usize,
+    term: TermKind,
+}
+
+#[derive(Debug)]
+enum TermKind {
+    Atom(String),
+    Int(String),
+    Compound(String, Vec<Term>),
+    List(Vec<Term>),
+}
+
+impl Term {
+    fn as_list(&self) -> Option<&[Term]> {
+        match &self.term {
+            TermKind::List(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    fn as_binary(&self, op: &str) -> Option<(&Term, &Term)> {
+        match &self.term {
+            TermKind::Compound(name, args) if name == op && args.len() == 2 => {
+                Some((&args[0], &args[1]))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Parser for the subset of Prolog terms used by the FSA utilities.
+struct TermParser<'a> {
+    chars: Peekable<Chars<'a>>,
+    line: usize,
+}
+
+impl<'a> TermParser<'a> {
+    fn new(data: &'a str) -> Self {
+        TermParser {
+            chars: data.chars().peekable(),
+            line: 1,
+        }
+    }
+
+    fn peek_char(&mut self) -> Option<char> {
+        self.chars.peek().copied()
+    }
+
+    fn next_char(&mut self) -> Option<char> {
+        let ch = self.chars.next();
+        if ch == Some('\n') {
+            self.line += 1;
+        }
+        ch
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(ch) = self.peek_char() {
+            if ch.is_whitespace() {
+                self.next_char();
+            } else if ch == '%' {
+                while !matches!(self.next_char(), Some('\n') | None) {}
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), TokenizerError> {
+        self.skip_whitespace();
+        match self.next_char() {
+            Some(ch) if ch == expected => Ok(()),
+            _ => Err(import_error(self.line, format!("expected '{}'", expected))),
+        }
+    }
+
+    fn parse_term(&mut self) -> Result<Term, TokenizerError> {
+        let left = self.parse_primary()?;
+
+        self.skip_whitespace();
+        if self.peek_char() != Some('/') {
+            return Ok(left);
+        }
+        self.next_char();
+
+        let right = self.parse_primary()?;
+        Ok(Term {
+            line: left.line,
+            term: TermKind::Compound("/".to_owned(), vec![left, right]),
+        })
+    }
+
+    fn parse_primary(&mut self) -> Result<Term, TokenizerError> {
+        self.skip_whitespace();
+        let line = self.line;
+
+        let name = match self.peek_char() {
+            Some('[') => {
+                self.next_char();
+                let items = self.parse_args(']')?;
+                return Ok(Term {
+                    line,
+                    term: TermKind::List(items),
+                });
+            }
+            Some(ch) if ch.is_ascii_digit() => {
+                let int = self.take_while(|ch| ch.is_ascii_digit());
+                return Ok(Term {
+                    line,
+                    term: TermKind::Int(int),
+                });
+            }
+            Some('\'') => self.parse_quoted()?,
+            Some(ch) if ch.is_lowercase() => {
+                self.take_while(|ch| ch.is_alphanumeric() || ch == '_')
+            }
+            Some(ch) if is_symbol_char(ch) => self.take_while(is_symbol_char),
+            _ => return Err(import_error(line, "expected a term")),
+        };
+
+        if self.peek_char() == Some('(') {
+            self.next_char();
+            let args = self.parse_args(')')?;
+            return Ok(Term {
+                line,
+                term: TermKind::Compound(name, args),
+            });
+        }
+
+        Ok(Term {
+            line,
+            term: TermKind::Atom(name),
+        })
+    }
+
+    fn parse_args(&mut self, close: char) -> Result<Vec<Term>, TokenizerError> {
+        let mut args = Vec::new();
+
+        self.skip_whitespace();
+        if self.peek_char() == Some(close) {
+            self.next_char();
+            return Ok(args);
+        }
+
+        loop {
+            args.push(self.parse_term()?);
+            self.skip_whitespace();
+            match self.next_char() {
+                Some(',') => continue,
+                Some(ch) if ch == close => return Ok(args),
+                _ => {
+                    return Err(import_error(
+                        self.line,
+                        format!("expected ',' or '{}'", close),
+                    ))
+                }
+            }
+        }
+    }
+
+    fn parse_quoted(&mut self) -> Result<String, TokenizerError> {
+        self.expect('\'')?;
+
+        let mut atom = String::new();
+        loop {
+            match self.next_char() {
+                Some('\'') if self.peek_char() == Some('\'') => {
+                    self.next_char();
+                    atom.push('\'');
+                }
+                Some('\'') => return Ok(atom),
+                Some('\\') => match self.next_char() {
+                    Some('n') => atom.push('\n'),
+                    Some('t') => atom.push('\t'),
+                    Some(ch) => atom.push(ch),
+                    None => break,
+                },
+                Some(ch) => atom.push(ch),
+                None => break,
+            }
+        }
+
+        Err(import_error(self.line, "unterminated quoted atom"))
+    }
+
+    fn take_while(&mut self, pred: impl Fn(char) -> bool) -> String {
+        let mut taken = String::new();
+        while let Some(ch) = self.peek_char() {
+            if !pred(ch) {
+                break;
+            }
+            taken.push(ch);
+            self.next_char();
+        }
+        taken
+    }
+}
+
+fn is_symbol_char(ch: char) -> bool {
+    "+-*\\^<>=~:.?@#&$".contains(ch)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::BufReader;
+
+    use crate::{ExportFormat, FiniteStateTokenizer, Subgraph, Tokenizer, TokenizerError};
+
+    const TEXT: &str = "Dit is een zin. En dit is nog een zin, met 'quotes'... Ünïcödé!";
+
+    #[test]
+    fn att_round_trip() {
+        let read = BufReader::new(File::open("testdata/toy.proto").unwrap());
+        let tokenizer = FiniteStateTokenizer::from_buf_read(read).unwrap();
+
+        let mut att = Vec::new();
+        tokenizer
+            .export(&mut att, ExportFormat::Att, Subgraph::default())
+            .unwrap();
+        let imported = FiniteStateTokenizer::from_att(&*att).unwrap();
+
+        assert_eq!(imported.validate(), Ok(()));
+        assert_eq!(
+            imported.tokenize(TEXT).unwrap(),
+            tokenizer.tokenize(TEXT).unwrap()
+        );
+    }
+
+    #[test]
+    fn att_unknown_symbols_and_final_outputs() {
+        let att = "0\t0\ta\ta\n\
+                   0\t1\t@_SPACE_@\t<newline>\n\
+                   0\t0\t<unk>\t[<unk>]\n\
+                   0\t2\t@0@\t.\n\
+                   1\t0\t<other>\t?\n\
+                   1\t0\tb\tb\n\
+                   2\t0.0\n";
+        let tokenizer = FiniteStateTokenizer::from_att(att.as_bytes()).unwrap();
+        assert_eq!(tokenizer.validate(), Ok(()));
+        assert_eq!(tokenizer.tokenize_raw("a bé".chars()).unwrap(), "a\nb[é].");
+        assert_eq!(tokenizer.tokenize_raw("a é".chars()).unwrap(), "a\n?.");
+    }
+
+    #[test]
+    fn att_rejects_nondeterminism() {
+        let att = "0\t1\ta\ta\n0\t2\ta\tb\n1\n2\n";
+        assert!(matches!(
+            FiniteStateTokenizer::from_att(att.as_bytes()),
+            Err(TokenizerError::ImportError { line: 2, .. })
+        ));
+
+        let att = "0\t1\t<unk>\t<unk>\n0\t1\t<other>\t<eps>\n1\n";
+        assert!(matches!(
+            FiniteStateTokenizer::from_att(att.as_bytes()),
+            Err(TokenizerError::ImportError { line: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn att_rejects_nul_input() {
+        let att = "0\t0\t<other>\t?\n0\t0\t<U+0000>\t<eps>\n0\n";
+        assert!(matches!(
+            FiniteStateTokenizer::from_att(att.as_bytes()),
+            Err(TokenizerError::ImportError { line: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn att_rejects_epsilon_transitions() {
+        let att = "0\t1\t<eps>\ta\n1\t0\tb\tb\n1\n";
+        assert!(matches!(
+            FiniteStateTokenizer::from_att(att.as_bytes()),
+            Err(TokenizerError::ImportError { line: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn fsa_prolog() {
+        let prolog = "% begin sigma and symbols
+fa(
+r(fsa_preds),
+% number of states
+3,
+[0],
+[2/'.'],
+[trans(0,a/a,1),
+ trans(0,' '/[],0),
+ trans(0,in([x,y])/'<xy>',1),
+ trans(0,$@(not_in([a,x,y,' '])),0),
+ trans(1,' '/'\\n',0)],
+[jump(1,2)]).
+";
+        let tokenizer = FiniteStateTokenizer::from_fsa_prolog(prolog.as_bytes()).unwrap();
+        assert_eq!(tokenizer.validate(), Ok(()));
+        assert_eq!(
+            tokenizer.tokenize_raw("ba y bx".chars()).unwrap(),
+            "ba\n<xy>\nb<xy>."
+        );
+    }
+
+    #[test]
+    fn fsa_prolog_expands_predicates() {
+        // The predicates also match known characters, b is matched by
+        // not_in([a]) and a by not_in([b]).
+        let prolog = "fa(r(fsa_preds),2,[0],[0],
+[trans(0,a/'A',1),
+ trans(0,$@(not_in([a])),0),
+ trans(1,b/'B',0),
+ trans(1,not_in([b])/'!',0)],[]).";
+        let tokenizer = FiniteStateTokenizer::from_fsa_prolog(prolog.as_bytes()).unwrap();
+        assert_eq!(tokenizer.validate(), Ok(()));
+        assert_eq!(
+            tokenizer.tokenize_raw("bcaabac".chars()).unwrap(),
+            "bcA!bA!"
+        );
+
+        let prolog = "fa(r(fsa_preds),2,[0],[0],
+[trans(0,a/a,1),
+ trans(1,true/'?',0)],[]).";
+        let tokenizer = FiniteStateTokenizer::from_fsa_prolog(prolog.as_bytes()).unwrap();
+        assert_eq!(tokenizer.validate(), Ok(()));
+        assert_eq!(tokenizer.tokenize_raw("aaab".chars()).unwrap(), "a?a?");
+    }
+
+    #[test]
+    fn fsa_prolog_syntax_error() {
+        let prolog = "fa(r(fsa_preds),\n2,\n[0],\n[1],\n[trans(0,a/a 1)],\n[]).";
+        assert!(matches!(
+            FiniteStateTokenizer::from_fsa_prolog(prolog.as_bytes()),
+            Err(TokenizerError::ImportError { line: 5, .. })
+        ));
+    }
+}
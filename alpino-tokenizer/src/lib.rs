@@ -24,19 +24,61 @@
 //!        vec!["Groningen", "heeft", "veel", "bezienswaardigheden", "."]]);
 //! ```
 
+mod alignment;
+pub use alignment::{Edit, EditAlignment};
+
 mod alpino;
 pub use alpino::AlpinoTokenizer;
 
+mod batch;
+pub use batch::map_parallel;
+
+mod builder;
+pub use builder::TransducerBuilder;
+
+mod compact;
+
+mod export;
+pub use export::{ExportFormat, Subgraph};
+
 mod fst;
-pub use fst::FiniteStateTokenizer;
+pub use fst::{FiniteStateTokenizer, Recovery};
+
+mod import;
+
+mod offsets;
+pub use offsets::{Sentence, Span, Token};
+
+mod pipeline;
+pub use pipeline::{Pipeline, RegexRule, TextProcessor};
 
 mod preproc;
+pub use preproc::AddEnumerationMarkers;
 
 mod postproc;
+pub use postproc::{
+    FixDashes, FixNewsArticleOpening, FixParens, FixQuotes, RemoveEnumerationMarkers,
+};
+
+mod session;
+pub use session::TokenizerSession;
 
 mod small_string;
 
+mod state_graph;
+pub use state_graph::BuildError;
+
+mod stream;
+
+mod tokenized;
+pub use tokenized::{Sentences, TokenizedSentence, TokenizedText, Tokens};
+
 mod tokenizer;
 pub use tokenizer::{Tokenizer, TokenizerError};
 
+mod transducer;
+
 mod util;
+
+mod validate;
+pub use validate::ValidationError;
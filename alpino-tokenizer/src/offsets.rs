@@ -0,0 +1,278 @@
+use std::ops::Range;
+
+use crate::alignment::{output_alignment, EditAlignment};
+use crate::tokenized::TokenizedText;
+
+/// Span of a token or sentence in the input text.
+///
+/// A span is stored both as byte offsets, which can be used to slice the
+/// input `&str`, and as character offsets.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Span {
+    /// Byte offset of the start of the span.
+    pub byte_start: usize,
+
+    /// Byte offset of the end of the span (exclusive).
+    pub byte_end: usize,
+
+    /// Character offset of the start of the span.
+    pub char_start: usize,
+
+    /// Character offset of the end of the span (exclusive).
+    pub char_end: usize,
+}
+
+impl Span {
+    /// Byte range of the span.
+    pub fn bytes(&self) -> Range<usize> {
+        self.byte_start..self.byte_end
+    }
+
+    /// Character range of the span.
+    pub fn chars(&self) -> Range<usize> {
+        self.char_start..self.char_end
+    }
+
+    /// Check whether the span is empty.
+    pub fn is_empty(&self) -> bool {
+        self.byte_start == self.byte_end
+    }
+}
+
+/// Token with its span in the input text.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Token {
+    text: String,
+    span: Span,
+    space_after: bool,
+}
+
+impl Token {
+    /// Construct a token.
+    ///
+    /// The token is assumed to be followed by whitespace, use
+    /// `with_space_after` to change this.
+    pub fn new(text: impl Into<String>, span: Span) -> Self {
+        Token {
+            text: text.into(),
+            span,
+            space_after: true,
+        }
+    }
+
+    /// Set whether the token is followed by whitespace in the input text.
+    pub fn with_space_after(mut self, space_after: bool) -> Self {
+        self.space_after = space_after;
+        self
+    }
+
+    /// Get the token text.
+    ///
+    /// The token text is the output of the tokenizer, which does not
+    /// necessarily equal the input text covered by the token span.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Get the span of the token in the input text.
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    /// Check whether the token is followed by whitespace in the input text.
+    ///
+    /// The last token of the input text is always considered to be
+    /// followed by whitespace.
+    pub fn space_after(&self) -> bool {
+        self.space_after
+    }
+
+    /// Get the token text, consuming the token.
+    pub fn into_text(self) -> String {
+        self.text
+    }
+}
+
+/// Sentence with its span in the input text.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Sentence {
+    tokens: Vec<Token>,
+    span: Span,
+}
+
+impl Sentence {
+    /// Construct a sentence.
+    ///
+    /// The sentence span covers the spans of the first and last token.
+    pub fn new(tokens: Vec<Token>) -> Self {
+        let span = match (tokens.first(), tokens.last()) {
+            (Some(first), Some(last)) => Span {
+                byte_start: first.span.byte_start,
+                byte_end: last.span.byte_end,
+                char_start: first.span.char_start,
+                char_end: last.span.char_end,
+            },
+            _ => Span::default(),
+        };
+
+        Sentence { tokens, span }
+    }
+
+    /// Get the tokens of the sentence.
+    pub fn tokens(&self) -> &[Token] {
+        &self.tokens
+    }
+
+    /// Get the span of the sentence in the input text.
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    /// Get the tokens, consuming the sentence.
+    pub fn into_tokens(self) -> Vec<Token> {
+        self.tokens
+    }
+}
+
+/// Align tokenizer output with the input text.
+///
+/// The tokenizer output is aligned with the input text using
+/// `output_alignment`.
+pub(crate) fn align(text: &str, tokenized: &TokenizedText) -> Vec<Sentence> {
+    align_with(text, tokenized, &output_alignment(text, tokenized.as_str()))
+}
+
+/// Align tokenizer output with the input text using an edit alignment.
+///
+/// `alignment` is the alignment of the tokenizer output with `text`.
+pub(crate) fn align_with(
+    text: &str,
+    tokenized: &TokenizedText,
+    alignment: &EditAlignment,
+) -> Vec<Sentence> {
+    let char_starts = text.char_indices().map(|(idx, _)| idx).collect::<Vec<_>>();
+    let char_offset = |byte_offset| char_starts.partition_point(|&start| start < byte_offset);
+
+    tokenized
+        .sentences()
+        .map(|sent| {
+            Sentence::new(
+                sent.token_ranges()
+                    .map(|(token, range)| {
+                        let bytes = alignment.project(range);
+                        let space_after = text[bytes.end..]
+                            .chars()
+                            .next()
+                            .map(char::is_whitespace)
+                            .unwrap_or(true);
+                        let span = Span {
+                            byte_start: bytes.start,
+                            byte_end: bytes.end,
+                            char_start: char_offset(bytes.start),
+                            char_end: char_offset(bytes.end),
+                        };
+                        Token::new(token, span).with_space_after(space_after)
+                    })
+                    .collect(),
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{align, Span};
+    use crate::tokenized::TokenizedText;
+
+    fn spans(text: &str, tokenized: &str) -> Vec<Vec<Span>> {
+        align(text, &TokenizedText::new(tokenized.to_owned()))
+            .into_iter()
+            .map(|sent| sent.tokens().iter().map(|t| t.span()).collect())
+            .collect()
+    }
+
+    fn span(byte_start: usize, byte_end: usize, char_start: usize, char_end: usize) -> Span {
+        Span {
+            byte_start,
+            byte_end,
+            char_start,
+            char_end,
+        }
+    }
+
+    #[test]
+    fn align_inserted_whitespace() {
+        assert_eq!(
+            spans("Hallo wereld. Tot ziens!", "Hallo wereld .\nTot ziens !"),
+            vec![
+                vec![span(0, 5, 0, 5), span(6, 12, 6, 12), span(12, 13, 12, 13),],
+                vec![
+                    span(14, 17, 14, 17),
+                    span(18, 23, 18, 23),
+                    span(23, 24, 23, 24),
+                ]
+            ]
+        );
+    }
+
+    #[test]
+    fn align_removed_characters() {
+        assert_eq!(
+            spans(
+                "Één\u{a0}\u{200b}zin\tmet\u{feff}tab.",
+                "Één zin met\u{feff}tab ."
+            ),
+            vec![vec![
+                span(0, 5, 0, 3),
+                span(10, 13, 5, 8),
+                span(14, 23, 9, 16),
+                span(23, 24, 16, 17),
+            ]]
+        );
+
+        assert_eq!(
+            spans("foo\u{200b}bar", "foobar"),
+            vec![vec![span(0, 9, 0, 7)]]
+        );
+    }
+
+    #[test]
+    fn align_rewritten_characters() {
+        assert_eq!(
+            spans("1# foo", "1. foo"),
+            vec![vec![span(0, 2, 0, 2), span(3, 6, 3, 6)]]
+        );
+    }
+
+    fn spaces_after(text: &str, tokenized: &str) -> Vec<Vec<bool>> {
+        align(text, &TokenizedText::new(tokenized.to_owned()))
+            .into_iter()
+            .map(|sent| sent.tokens().iter().map(|t| t.space_after()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn align_space_after() {
+        assert_eq!(
+            spaces_after(
+                "Hallo, wereld.Tot\u{a0}\"ziens\"!",
+                "Hallo , wereld .\nTot \" ziens \" !"
+            ),
+            vec![
+                vec![false, true, false, false],
+                vec![true, false, false, false, true]
+            ]
+        );
+
+        assert_eq!(
+            spaces_after("foo\u{200b}bar", "foo bar"),
+            vec![vec![false, true]]
+        );
+    }
+
+    #[test]
+    fn align_empty() {
+        assert_eq!(spans("", ""), vec![vec![span(0, 0, 0, 0)]]);
+        assert_eq!(spans("  ", ""), vec![vec![span(0, 0, 0, 0)]]);
+    }
+}
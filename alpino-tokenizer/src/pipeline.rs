@@ -0,0 +1,405 @@
+use std::fmt;
+use std::mem;
+use std::sync::Arc;
+
+use regex::{Regex, Replacer};
+
+use crate::alignment::EditAlignment;
+use crate::postproc::{
+    FixDashes, FixNewsArticleOpening, FixParens, FixQuotes, RemoveEnumerationMarkers,
+};
+use crate::preproc::AddEnumerationMarkers;
+
+/// Text processor.
+///
+/// Text processors rewrite the text before tokenization or the tokenizer
+/// output after tokenization. Processors are combined in a [`Pipeline`].
+/// In the tokenizer output, tokens are separated by a space and sentences
+/// by a newline.
+pub trait TextProcessor: Send + Sync {
+    /// Name of the processor.
+    ///
+    /// The name is used to find the processor in a pipeline.
+    fn name(&self) -> &str;
+
+    /// Process `text`, writing the result to `dst`.
+    ///
+    /// Returns `false` when the processor does not change `text`. In
+    /// that case, `dst` does not have to be written.
+    fn process(&self, text: &str, dst: &mut String) -> bool;
+
+    /// Process `text`, writing the result to `dst` and recording the edits.
+    ///
+    /// The edits are added to `alignment`, which is empty when this method
+    /// is called. The default implementation computes the edits from the
+    /// differences between `text` and the result of `process`. Processors
+    /// that know which parts of the text they rewrite should override this
+    /// method to record the edits directly.
+    fn process_aligned(&self, text: &str, dst: &mut String, alignment: &mut EditAlignment) -> bool {
+        let changed = self.process(text, dst);
+        if changed {
+            alignment.push_diff(text, 0, dst, 0);
+        }
+        changed
+    }
+}
+
+/// Pipeline of text processors.
+///
+/// The processors are applied in order. The pipelines of the Alpino
+/// tokenizer can be modified through `AlpinoTokenizer::preprocessing_mut`
+/// and `AlpinoTokenizer::postprocessing_mut`. For instance, the following
+/// example removes a built-in rule and adds a custom rule:
+///
+/// ```
+/// use std::fs::File;
+/// use std::io::BufReader;
+///
+/// use alpino_tokenizer::{AlpinoTokenizer, RegexRule, Tokenizer};
+/// use regex::Regex;
+///
+/// let read = BufReader::new(File::open("testdata/toy.proto").unwrap());
+/// let mut tokenizer = AlpinoTokenizer::from_buf_read(read).unwrap();
+///
+/// let postprocessing = tokenizer.postprocessing_mut();
+/// assert!(postprocessing.remove("fix_news_article_opening").is_some());
+/// postprocessing.push(RegexRule::new(
+///     "euro",
+///     Regex::new("€ ([0-9]+)").unwrap(),
+///     "$1 euro",
+/// ));
+///
+/// assert_eq!(
+///     tokenizer.tokenize("ARTIKEL 1 - Dit kost € 10.").unwrap(),
+///     vec![vec!["ARTIKEL", "1", "-", "Dit", "kost", "10", "euro", "."]]
+/// );
+/// ```
+#[derive(Clone, Default)]
+pub struct Pipeline {
+    processors: Vec<Arc<dyn TextProcessor>>,
+}
+
+impl Pipeline {
+    /// Construct an empty pipeline.
+    pub fn new() -> Self {
+        Pipeline::default()
+    }
+
+    /// Construct the default preprocessing pipeline of the Alpino tokenizer.
+    ///
+    /// This pipeline consists of:
+    ///
+    /// * [`AddEnumerationMarkers`]
+    pub fn alpino_preprocessing() -> Self {
+        let mut pipeline = Pipeline::new();
+        pipeline.push(AddEnumerationMarkers);
+        pipeline
+    }
+
+    /// Construct the default postprocessing pipeline of the Alpino tokenizer.
+    ///
+    /// This pipeline consists of:
+    ///
+    /// * [`FixQuotes`]
+    /// * [`FixParens`]
+    /// * [`RemoveEnumerationMarkers`]
+    /// * [`FixNewsArticleOpening`]
+    /// * [`FixDashes`]
+    pub fn alpino_postprocessing() -> Self {
+        let mut pipeline = Pipeline::new();
+        pipeline
+            .push(FixQuotes)
+            .push(FixParens)
+            .push(RemoveEnumerationMarkers)
+            .push(FixNewsArticleOpening)
+            .push(FixDashes);
+        pipeline
+    }
+
+    /// Add a processor to the end of the pipeline.
+    pub fn push(&mut self, processor: impl TextProcessor + 'static) -> &mut Self {
+        self.processors.push(Arc::new(processor));
+        self
+    }
+
+    /// Insert a processor at position `index`.
+    ///
+    /// Panics when `index > len`.
+    pub fn insert(&mut self, index: usize, processor: impl TextProcessor + 'static) -> &mut Self {
+        self.processors.insert(index, Arc::new(processor));
+        self
+    }
+
+    /// Remove the first processor with the given name.
+    ///
+    /// Returns the removed processor, or `None` if the pipeline does not
+    /// contain a processor with that name.
+    pub fn remove(&mut self, name: &str) -> Option<Arc<dyn TextProcessor>> {
+        let index = self.position(name)?;
+        Some(self.processors.remove(index))
+    }
+
+    /// Move the first processor with the given name to position `index`.
+    ///
+    /// Returns `false` if the pipeline does not contain a processor with
+    /// that name. Panics when `index >= len`.
+    pub fn move_to(&mut self, name: &str, index: usize) -> bool {
+        assert!(
+            index < self.len(),
+            "Index {} out of bounds for pipeline of length {}",
+            index,
+            self.len()
+        );
+
+        match self.remove(name) {
+            Some(processor) => {
+                self.processors.insert(index, processor);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Get the position of the first processor with the given name.
+    pub fn position(&self, name: &str) -> Option<usize> {
+        self.processors
+            .iter()
+            .position(|processor| processor.name() == name)
+    }
+
+    /// Get the names of the processors, in pipeline order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.processors.iter().map(|processor| processor.name())
+    }
+
+    /// Get the number of processors.
+    pub fn len(&self) -> usize {
+        self.processors.len()
+    }
+
+    /// Check whether the pipeline is empty.
+    pub fn is_empty(&self) -> bool {
+        self.processors.is_empty()
+    }
+
+    /// Apply the pipeline to `text`.
+    pub fn process(&self, text: &str) -> String {
+        let mut output = String::new();
+        self.process_into(text, &mut String::new(), &mut output);
+        output
+    }
+
+    /// Apply the pipeline to `text`, recording the edits.
+    ///
+    /// Returns the result and its alignment with `text`.
+    pub fn process_aligned(&self, text: &str) -> (String, EditAlignment) {
+        let mut output = String::new();
+        let alignment = self.process_aligned_into(text, &mut String::new(), &mut output);
+        (output, alignment)
+    }
+
+    /// Apply the pipeline to `text`, writing the result to `output`.
+    ///
+    /// `scratch` is used for intermediate results, so that the buffers can
+    /// be reused between calls.
+    pub(crate) fn process_into(&self, text: &str, scratch: &mut String, output: &mut String) {
+        output.clear();
+        output.push_str(text);
+
+        for processor in &self.processors {
+            if processor.process(output, scratch) {
+                mem::swap(output, scratch);
+            }
+        }
+    }
+
+    /// Apply the pipeline to `text`, writing the result to `output`.
+    ///
+    /// Returns the alignment of the result with `text`.
+    pub(crate) fn process_aligned_into(
+        &self,
+        text: &str,
+        scratch: &mut String,
+        output: &mut String,
+    ) -> EditAlignment {
+        output.clear();
+        output.push_str(text);
+
+        let mut alignment = EditAlignment::new();
+        let mut step_alignment = EditAlignment::new();
+        for processor in &self.processors {
+            step_alignment.clear();
+            if processor.process_aligned(output, scratch, &mut step_alignment) {
+                mem::swap(output, scratch);
+                alignment = alignment.compose(&step_alignment);
+            }
+        }
+
+        alignment
+    }
+}
+
+impl fmt::Debug for Pipeline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.names()).finish()
+    }
+}
+
+/// Text processor that replaces all matches of a regular expression.
+///
+/// The replacement can refer to capture groups, see
+/// `regex::Regex::replace_all`.
+#[derive(Clone, Debug)]
+pub struct RegexRule {
+    name: String,
+    regex: Regex,
+    replacement: String,
+}
+
+impl RegexRule {
+    /// Construct a rule that replaces matches of `regex` by `replacement`.
+    pub fn new(name: impl Into<String>, regex: Regex, replacement: impl Into<String>) -> Self {
+        RegexRule {
+            name: name.into(),
+            regex,
+            replacement: replacement.into(),
+        }
+    }
+}
+
+impl TextProcessor for RegexRule {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn process(&self, text: &str, dst: &mut String) -> bool {
+        replace_into(&self.regex, text, self.replacement.as_str(), dst, None)
+    }
+
+    fn process_aligned(&self, text: &str, dst: &mut String, alignment: &mut EditAlignment) -> bool {
+        replace_into(
+            &self.regex,
+            text,
+            self.replacement.as_str(),
+            dst,
+            Some(alignment),
+        )
+    }
+}
+
+/// Replace all matches of `re` in `text`, writing the result to `dst`.
+///
+/// Returns `false` without writing the text to `dst` when there are no
+/// matches. In contrast to `Regex::replace_all`, this allows reuse of
+/// the destination buffer. If `alignment` is provided, the edits of
+/// every match are added to it.
+pub(crate) fn replace_into(
+    re: &Regex,
+    text: &str,
+    mut replacement: impl Replacer,
+    dst: &mut String,
+    mut alignment: Option<&mut EditAlignment>,
+) -> bool {
+    let mut captures_iter = re.captures_iter(text).peekable();
+    if captures_iter.peek().is_none() {
+        return false;
+    }
+
+    dst.clear();
+
+    let mut last_end = 0;
+    for captures in captures_iter {
+        let m = captures.get(0).unwrap();
+        dst.push_str(&text[last_end..m.start()]);
+        let replacement_start = dst.len();
+        replacement.replace_append(&captures, dst);
+        if let Some(alignment) = alignment.as_deref_mut() {
+            alignment.push_diff(
+                m.as_str(),
+                m.start(),
+                &dst[replacement_start..],
+                replacement_start,
+            );
+        }
+        last_end = m.end();
+    }
+    dst.push_str(&text[last_end..]);
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use regex::Regex;
+
+    use super::{Pipeline, RegexRule, TextProcessor};
+
+    struct Uppercase;
+
+    impl TextProcessor for Uppercase {
+        fn name(&self) -> &str {
+            "uppercase"
+        }
+
+        fn process(&self, text: &str, dst: &mut String) -> bool {
+            dst.clear();
+            dst.push_str(&text.to_uppercase());
+            true
+        }
+    }
+
+    #[test]
+    fn default_pipelines() {
+        assert_eq!(
+            Pipeline::alpino_preprocessing().names().collect::<Vec<_>>(),
+            vec!["add_enumeration_markers"]
+        );
+        assert_eq!(
+            Pipeline::alpino_postprocessing()
+                .names()
+                .collect::<Vec<_>>(),
+            vec![
+                "fix_quotes",
+                "fix_parens",
+                "remove_enumeration_markers",
+                "fix_news_article_opening",
+                "fix_dashes"
+            ]
+        );
+    }
+
+    #[test]
+    fn processors_are_applied_in_order() {
+        let mut pipeline = Pipeline::new();
+        pipeline
+            .push(RegexRule::new("ab", Regex::new("a").unwrap(), "b"))
+            .push(Uppercase);
+        assert_eq!(pipeline.process("abc"), "BBC");
+
+        pipeline.insert(1, RegexRule::new("bc", Regex::new("b").unwrap(), "c"));
+        assert_eq!(pipeline.process("abc"), "CCC");
+
+        assert!(pipeline.move_to("uppercase", 0));
+        assert_eq!(
+            pipeline.names().collect::<Vec<_>>(),
+            ["uppercase", "ab", "bc"]
+        );
+        assert_eq!(pipeline.process("abc"), "ABC");
+
+        assert!(pipeline.remove("uppercase").is_some());
+        assert!(pipeline.remove("uppercase").is_none());
+        assert!(!pipeline.move_to("uppercase", 0));
+        assert_eq!(pipeline.process("abc"), "ccc");
+    }
+
+    #[test]
+    fn unchanged_text() {
+        let pipeline = Pipeline::new();
+        assert!(pipeline.is_empty());
+        assert_eq!(pipeline.process("abc"), "abc");
+
+        let rule = RegexRule::new("x", Regex::new("x").unwrap(), "y");
+        let mut dst = String::new();
+        assert!(!rule.process("abc", &mut dst));
+    }
+}
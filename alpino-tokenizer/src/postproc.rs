@@ -3,80 +3,216 @@ use std::borrow::Cow;
 use lazy_static::lazy_static;
 use regex::{Captures, Regex};
 
-// ik ga -zoals gezegd- naar huis -> ik ga - zoals gezegd - naar huis
-// but, 'huis- tuin- en keuken' should stay as-is
-fn fix_dashes(text: &str) -> Cow<str> {
+use crate::alignment::EditAlignment;
+use crate::pipeline::{replace_into, TextProcessor};
+use crate::preproc::is_enumeration_label;
+
+/// Add spaces around dashes that are used as parentheses.
+///
+/// For example, `ik ga -zoals gezegd- naar huis` is rewritten to
+/// `ik ga - zoals gezegd - naar huis`, but `huis- tuin- en keuken` is
+/// kept as-is.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FixDashes;
+
+impl TextProcessor for FixDashes {
+    fn name(&self) -> &str {
+        "fix_dashes"
+    }
+
+    fn process(&self, text: &str, dst: &mut String) -> bool {
+        fix_dashes(text, dst, None)
+    }
+
+    fn process_aligned(&self, text: &str, dst: &mut String, alignment: &mut EditAlignment) -> bool {
+        fix_dashes(text, dst, Some(alignment))
+    }
+}
+
+fn fix_dashes(text: &str, dst: &mut String, alignment: Option<&mut EditAlignment>) -> bool {
     lazy_static! {
         static ref RE: Regex = Regex::new(" -([^ ][^-]*[^ ])- ").unwrap();
     }
 
-    RE.replace_all(text, |captures: &Captures| {
-        let m = captures.get(0).unwrap();
-        let left = &text[..m.start()];
-        let right = &text[m.end()..];
-
-        if left.ends_with("en")
-            || left.ends_with("of")
-            || right.starts_with("en")
-            || right.starts_with("of")
-        {
-            Cow::Borrowed(&text[m.start()..m.end()])
-        } else {
-            let m = captures.get(1).unwrap();
-            Cow::Owned(format!(" - {} - ", &text[m.start()..m.end()]))
-        }
-    })
+    replace_into(
+        &RE,
+        text,
+        |captures: &Captures| {
+            let m = captures.get(0).unwrap();
+            let left = &text[..m.start()];
+            let right = &text[m.end()..];
+
+            if left.ends_with("en")
+                || left.ends_with("of")
+                || right.starts_with("en")
+                || right.starts_with("of")
+            {
+                Cow::Borrowed(&text[m.start()..m.end()])
+            } else {
+                let m = captures.get(1).unwrap();
+                Cow::Owned(format!(" - {} - ", &text[m.start()..m.end()]))
+            }
+        },
+        dst,
+        alignment,
+    )
 }
 
-// # AMSTERDAM - ... -> AMSTERDAM -\n...
-fn fix_news_article_opening(text: &str) -> Cow<str> {
+/// Split the opening of a news article into a separate sentence.
+///
+/// For example, `AMSTERDAM - De hoofdstad` is rewritten to
+/// `AMSTERDAM -\nDe hoofdstad`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FixNewsArticleOpening;
+
+impl TextProcessor for FixNewsArticleOpening {
+    fn name(&self) -> &str {
+        "fix_news_article_opening"
+    }
+
+    fn process(&self, text: &str, dst: &mut String) -> bool {
+        fix_news_article_opening(text, dst, None)
+    }
+
+    fn process_aligned(&self, text: &str, dst: &mut String, alignment: &mut EditAlignment) -> bool {
+        fix_news_article_opening(text, dst, Some(alignment))
+    }
+}
+
+fn fix_news_article_opening(
+    text: &str,
+    dst: &mut String,
+    alignment: Option<&mut EditAlignment>,
+) -> bool {
     lazy_static! {
         static ref RE: Regex =
             Regex::new("(?:^|\n)([[:upper:]]{2}[[:upper:]() /,0-9.-]* -+) ").unwrap();
     }
 
-    RE.replace_all(text, "$1\n")
+    replace_into(&RE, text, "$1\n", dst, alignment)
+}
+
+/// Remove the space after an opening parenthesis within a word.
+///
+/// For example, `( buiten)gewoon` is rewritten to `(buiten)gewoon`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FixParens;
+
+impl TextProcessor for FixParens {
+    fn name(&self) -> &str {
+        "fix_parens"
+    }
+
+    fn process(&self, text: &str, dst: &mut String) -> bool {
+        fix_parens(text, dst, None)
+    }
+
+    fn process_aligned(&self, text: &str, dst: &mut String, alignment: &mut EditAlignment) -> bool {
+        fix_parens(text, dst, Some(alignment))
+    }
 }
 
-// ( buiten)gewoon -> (buiten)gewoon
-fn fix_parens(text: &str) -> Cow<str> {
+fn fix_parens(text: &str, dst: &mut String, alignment: Option<&mut EditAlignment>) -> bool {
     lazy_static! {
         static ref RE: Regex = Regex::new("[(] ([[:lower:][:upper:]]+[)])").unwrap();
     }
 
-    RE.replace_all(text, "($1")
+    replace_into(&RE, text, "($1", dst, alignment)
 }
 
-// # ' top'-vorm -> 'top'-vorm
-fn fix_quotes(text: &str) -> Cow<str> {
+/// Remove the space after an opening quote within a word.
+///
+/// For example, `' top'-vorm` is rewritten to `'top'-vorm`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FixQuotes;
+
+impl TextProcessor for FixQuotes {
+    fn name(&self) -> &str {
+        "fix_quotes"
+    }
+
+    fn process(&self, text: &str, dst: &mut String) -> bool {
+        fix_quotes(text, dst, None)
+    }
+
+    fn process_aligned(&self, text: &str, dst: &mut String, alignment: &mut EditAlignment) -> bool {
+        fix_quotes(text, dst, Some(alignment))
+    }
+}
+
+fn fix_quotes(text: &str, dst: &mut String, alignment: Option<&mut EditAlignment>) -> bool {
     lazy_static! {
         static ref RE: Regex = Regex::new("([`'\"]) ([[:upper:][:lower:]]+[`'\"]-)").unwrap();
     }
 
-    RE.replace_all(text, "$1$2")
+    replace_into(&RE, text, "$1$2", dst, alignment)
 }
 
-pub fn postprocess(text: &str) -> String {
-    let text = fix_quotes(text);
-    let text = fix_parens(&text);
-    let text = remove_enumeration_markers(&text);
-    let text = fix_news_article_opening(&text);
-    let text = fix_dashes(&text);
+/// Replace the enumeration markers of [`AddEnumerationMarkers`] by periods.
+///
+/// Markers are replaced after numbers, lowercase letters, and lowercase
+/// Roman numerals. This processor cannot distinguish markers from `#`
+/// characters in the input, such as in `C#`. `AlpinoTokenizer` protects
+/// the `#` characters of the input while it applies the postprocessing
+/// pipeline.
+///
+/// [`AddEnumerationMarkers`]: crate::AddEnumerationMarkers
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RemoveEnumerationMarkers;
+
+impl TextProcessor for RemoveEnumerationMarkers {
+    fn name(&self) -> &str {
+        "remove_enumeration_markers"
+    }
+
+    fn process(&self, text: &str, dst: &mut String) -> bool {
+        remove_enumeration_markers(text, dst, None)
+    }
 
-    text.into_owned()
+    fn process_aligned(&self, text: &str, dst: &mut String, alignment: &mut EditAlignment) -> bool {
+        remove_enumeration_markers(text, dst, Some(alignment))
+    }
 }
 
-fn remove_enumeration_markers(text: &str) -> Cow<str> {
+fn remove_enumeration_markers(
+    text: &str,
+    dst: &mut String,
+    alignment: Option<&mut EditAlignment>,
+) -> bool {
     lazy_static! {
-        static ref RE: Regex = Regex::new("([0-9]+)#(\\s)").unwrap();
+        static ref RE: Regex = Regex::new("([0-9]+|\\b[a-z]+)#(\\s)").unwrap();
     }
 
-    RE.replace_all(text, "$1.$2")
+    replace_into(
+        &RE,
+        text,
+        |captures: &Captures| {
+            let label = &captures[1];
+            if label.as_bytes()[0].is_ascii_digit() || is_enumeration_label(label) {
+                format!("{}.{}", label, &captures[2])
+            } else {
+                captures[0].to_string()
+            }
+        },
+        dst,
+        alignment,
+    )
+}
+
+/// Join the sentences of tokenized `text` into a single sentence.
+pub(crate) fn join_sentences(text: &mut String) {
+    if text.contains('\n') {
+        *text = text.replace('\n', " ");
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::postprocess;
+    use crate::pipeline::Pipeline;
+
+    fn postprocess(text: &str) -> String {
+        Pipeline::alpino_postprocessing().process(text)
+    }
 
     #[test]
     fn fix_dashes() {
@@ -136,5 +272,17 @@ mod tests {
             postprocess("1# boter, 2# kaas en 3# eieren, 1# foo en 2# bar"),
             "1. boter, 2. kaas en 3. eieren, 1. foo en 2. bar"
         );
+
+        assert_eq!(
+            postprocess("a# boter , b# kaas en c# eieren"),
+            "a. boter , b. kaas en c. eieren"
+        );
+        assert_eq!(
+            postprocess("i# boter\nii# kaas\niv# eieren"),
+            "i. boter\nii. kaas\niv. eieren"
+        );
+
+        // Only markers after enumeration labels are replaced.
+        assert_eq!(postprocess("ab# iiii# Xa# "), "ab# iiii# Xa# ");
     }
 }
@@ -1,53 +1,684 @@
-use std::borrow::Cow;
+use std::collections::HashMap;
+use std::ops::Range;
 
 use lazy_static::lazy_static;
 use regex::Regex;
 
-// This function rewrites enumerations of the form
+use crate::alignment::EditAlignment;
+use crate::pipeline::TextProcessor;
+
+// Start of an enumeration item, such as `1# ` in `1# foo`.
+#[derive(Clone, Copy, Debug)]
+struct ItemStart {
+    // Offset of the item number.
+    number: usize,
+
+    // Offset after the marker and the whitespace that follows it.
+    text: usize,
+}
+
+// Candidate for the next item of an enumeration, such as ` 2. ` in
+// `1# foo, 2. bar`.
+#[derive(Clone, Copy, Debug)]
+struct ItemEnd {
+    // Offset of the non-word character that precedes the number.
+    boundary: usize,
+
+    // Offset of the period that follows the number.
+    period: usize,
+
+    // Offset after the match.
+    end: usize,
+}
+
+impl ItemEnd {
+    // The marked item is the start of the next item.
+    fn item_start(&self, number_len: usize) -> ItemStart {
+        ItemStart {
+            number: self.period - number_len,
+            text: self.end,
+        }
+    }
+}
+
+// Number followed by a period or marker.
+struct Number {
+    // Byte range of the digits.
+    digits: Range<usize>,
+
+    // The digits are preceded by a non-word character, with its offset.
+    boundary: Option<usize>,
+
+    // Marker: `.` or `#`.
+    marker: u8,
+
+    // Length of the whitespace character that follows the marker.
+    whitespace_len: Option<usize>,
+}
+
+impl Number {
+    fn text(&self) -> usize {
+        self.digits.end + 1 + self.whitespace_len.unwrap_or(0)
+    }
+}
+
+// Letters that are followed by a period and whitespace, such as `a. ` or
+// `iii. `. The letters are not preceded by a word character.
+struct Label {
+    // Byte range of the letters.
+    letters: Range<usize>,
+
+    // Offset of the non-word character that precedes the letters, `None`
+    // at the start of the text.
+    boundary: Option<usize>,
+
+    // Offset after the period and the whitespace that follows it.
+    text: usize,
+}
+
+// Kind of enumeration labels, besides numbers.
+#[derive(Clone, Copy, Debug)]
+enum LabelKind {
+    // Lowercase letters: a, b, c, ...
+    Letter,
+
+    // Lowercase Roman numerals: i, ii, iii, ...
+    Roman,
+}
+
+impl LabelKind {
+    // Get the item number of a label.
+    fn value(self, label: &str) -> Option<usize> {
+        match self {
+            LabelKind::Letter => match *label.as_bytes() {
+                [letter @ b'a'..=b'z'] => Some((letter - b'a') as usize + 1),
+                _ => None,
+            },
+            LabelKind::Roman => roman_value(label),
+        }
+    }
+
+    // Get the length of the label of an item number.
+    fn label_len(self, n: usize) -> usize {
+        match self {
+            LabelKind::Letter => 1,
+            LabelKind::Roman => to_roman(n).len(),
+        }
+    }
+}
+
+// Items found in a text.
+struct Items {
+    numbers: Vec<Number>,
+    labels: Vec<Label>,
+    newlines: Vec<usize>,
+}
+
+// Find the enumeration periods that should be marked.
+//
+// This function finds the enumerations of the form
 //
 // 1. foo, 2. bar en 3. baz
 //
-// to
+// which are rewritten to
 //
 // 1# foo, 2# bar en 3# baz
-fn add_enumeration_markers(text: &str) -> Cow<str> {
-    lazy_static! {
-        static ref RE: Regex = Regex::new("(\\s?1)[.](\\s.*?\\W2[.])").unwrap();
+//
+// The enumerations were originally marked by first applying the regular
+// expression `(\s?1)[.](\s.*?\W2[.])` and then, as long as there were
+// changes, `({n}#\s.*?\W{n+1})[.](\s)` for n = 1, 2, .... This function
+// has the same semantics, but finds all candidate items in a single pass
+// and then matches every enumeration step against the candidates.
+//
+// Enumerations with letters or Roman numerals, such as
+//
+// a. foo, b. bar en c. baz
+// i. foo, ii. bar en iii. baz
+//
+// are marked in the same manner. However, their labels must not be preceded
+// by a word character and all items must be followed by whitespace.
+//
+// Returns the offsets of the periods, in text order.
+fn enumeration_periods(text: &str) -> Vec<usize> {
+    let items = scan_items(text);
+
+    // Offset of the first newline at or after `offset`.
+    let newlines = &items.newlines;
+    let next_newline = |offset: usize| {
+        newlines
+            .get(newlines.partition_point(|&newline| newline < offset))
+            .copied()
+            .unwrap_or(usize::MAX)
+    };
+
+    let mut periods = number_enumeration_periods(text, &items.numbers, next_newline);
+    for kind in [LabelKind::Letter, LabelKind::Roman] {
+        periods.extend(label_enumeration_periods(
+            text,
+            &items.labels,
+            kind,
+            next_newline,
+        ));
+    }
+
+    // An item can be both a letter and a Roman numeral, e.g. `i.`.
+    periods.sort_unstable();
+    periods.dedup();
+    periods
+}
+
+// Find the periods of numbered enumerations.
+fn number_enumeration_periods(
+    text: &str,
+    numbers: &[Number],
+    next_newline: impl Fn(usize) -> usize + Copy,
+) -> Vec<usize> {
+    // Step 1: mark `1.` when it is followed by an item `2.`. The period of
+    // the item `2.` does not need to be followed by whitespace.
+    let starts = numbers
+        .iter()
+        .filter(|number| {
+            number.marker == b'.'
+                && number.whitespace_len.is_some()
+                && text.as_bytes()[number.digits.end - 1] == b'1'
+        })
+        .map(|number| ItemStart {
+            number: number.digits.end - 1,
+            text: number.text(),
+        });
+    let ends = numbers
+        .iter()
+        .filter(|number| number.marker == b'.' && &text[number.digits.clone()] == "2")
+        .filter_map(|number| {
+            Some(ItemEnd {
+                boundary: number.boundary?,
+                period: number.digits.end,
+                end: number.digits.end + 1,
+            })
+        })
+        .collect::<Vec<_>>();
+    let mut periods = match_items(starts, &ends, next_newline)
+        .map(|(start, _)| start.number + 1)
+        .collect::<Vec<_>>();
+    if periods.is_empty() {
+        return periods;
+    }
+
+    // Item starts that are in the text (after step 1) by item number. Since
+    // the regular expressions do not require a word boundary before the
+    // item number, the number can be a suffix of a longer number.
+    let mut marked_starts: HashMap<usize, Vec<ItemStart>> = HashMap::new();
+
+    // Item ends by item number.
+    let mut item_ends: HashMap<usize, Vec<ItemEnd>> = HashMap::new();
+    for number in numbers {
+        let marked = number.marker == b'#'
+            || (number.marker == b'.' && periods.binary_search(&number.digits.end).is_ok());
+        let whitespace_len = match number.whitespace_len {
+            Some(whitespace_len) => whitespace_len,
+            None => continue,
+        };
+
+        if marked {
+            let suffixes_start = number
+                .digits
+                .start
+                .max(number.digits.end.saturating_sub(MAX_NUMBER_LEN));
+            for suffix_start in suffixes_start..number.digits.end {
+                if let Some(n) = parse_number(&text[suffix_start..number.digits.end]) {
+                    marked_starts.entry(n).or_default().push(ItemStart {
+                        number: suffix_start,
+                        text: number.text(),
+                    });
+                }
+            }
+        } else if let (Some(boundary), Some(n)) =
+            (number.boundary, parse_number(&text[number.digits.clone()]))
+        {
+            item_ends.entry(n).or_default().push(ItemEnd {
+                boundary,
+                period: number.digits.end,
+                end: number.digits.end + 1 + whitespace_len,
+            });
+        }
     }
 
-    let mut text = RE.replace_all(text, "$1#$2");
+    // Step n: mark `{n+1}.` when it follows an item `{n}#`.
+    let mut prev_starts = Vec::new();
+    for n in 1.. {
+        let ends = match item_ends.get(&(n + 1)) {
+            Some(ends) => ends,
+            None => break,
+        };
+
+        let mut starts = marked_starts.remove(&n).unwrap_or_default();
+        if !prev_starts.is_empty() {
+            starts.extend_from_slice(&prev_starts);
+            starts.sort_unstable_by_key(|start| start.number);
+        }
+
+        let number_len = count_digits(n + 1);
+        prev_starts.clear();
+        prev_starts.extend(
+            match_items(starts.into_iter(), ends, next_newline)
+                .map(|(_, end)| end.item_start(number_len)),
+        );
+        if prev_starts.is_empty() {
+            break;
+        }
+
+        periods.extend(prev_starts.iter().map(|start| start.number + number_len));
+    }
+
+    periods
+}
 
-    if let text @ Cow::Borrowed(_) = text {
-        return text;
+// Find the periods of enumerations with labels of the given kind.
+fn label_enumeration_periods(
+    text: &str,
+    labels: &[Label],
+    kind: LabelKind,
+    next_newline: impl Fn(usize) -> usize + Copy,
+) -> Vec<usize> {
+    let mut items: HashMap<usize, Vec<&Label>> = HashMap::new();
+    for label in labels {
+        if let Some(n) = kind.value(&text[label.letters.clone()]) {
+            items.entry(n).or_default().push(label);
+        }
     }
 
-    let mut prev = 1;
-    let mut next = 2;
+    let mut starts = items
+        .get(&1)
+        .into_iter()
+        .flatten()
+        .map(|label| ItemStart {
+            number: label.letters.start,
+            text: label.text,
+        })
+        .collect::<Vec<_>>();
 
-    loop {
-        let next_expr = Regex::new(&format!("({}#\\s.*?\\W{})[.](\\s)", prev, next))
-            .expect("Invalid enumeration expression.");
-        let text_after = next_expr.replace_all(&text, "$1#$2");
+    // Step n: mark `{n+1}.` when it follows an item `{n}.`. In the first
+    // step, the first item is marked as well.
+    let mut periods = Vec::new();
+    for n in 1.. {
+        let ends = items
+            .get(&(n + 1))
+            .into_iter()
+            .flatten()
+            .filter_map(|label| {
+                Some(ItemEnd {
+                    boundary: label.boundary?,
+                    period: label.letters.end,
+                    end: label.text,
+                })
+            })
+            .collect::<Vec<_>>();
 
-        if let Cow::Borrowed(_) = text_after {
+        let label_len = kind.label_len(n + 1);
+        let matches = match_items(starts.iter().copied(), &ends, next_newline)
+            .map(|(start, end)| (start, end.item_start(label_len)))
+            .collect::<Vec<_>>();
+        if matches.is_empty() {
             break;
         }
 
-        text = Cow::Owned(text_after.into_owned());
-        prev += 1;
-        next += 1;
+        if n == 1 {
+            // The label of the first item is a single letter.
+            periods.extend(matches.iter().map(|(start, _)| start.number + 1));
+        }
+        periods.extend(matches.iter().map(|(_, next)| next.number + label_len));
+
+        starts = matches.into_iter().map(|(_, next)| next).collect();
     }
 
-    text
+    periods
 }
 
-pub fn preprocess(text: &str) -> Cow<str> {
-    add_enumeration_markers(text)
+// Match item starts with item ends.
+//
+// Returns the matched starts and ends. Every start is matched with the
+// first end that follows it on the same line. The semantics are those
+// of non-overlapping leftmost-first regular expression matching: starts
+// that are covered by a previous match are skipped. `starts` and `ends`
+// must be sorted.
+fn match_items<'a>(
+    starts: impl Iterator<Item = ItemStart> + 'a,
+    ends: &'a [ItemEnd],
+    next_newline: impl Fn(usize) -> usize + 'a,
+) -> impl Iterator<Item = (ItemStart, &'a ItemEnd)> + 'a {
+    let mut offset = 0;
+    let mut end_idx = 0;
+    starts.filter_map(move |start| {
+        if start.number < offset {
+            return None;
+        }
+
+        while ends.get(end_idx)?.boundary < start.text {
+            end_idx += 1;
+        }
+
+        // The text between the start and the end cannot contain a newline,
+        // but the non-word character before the number of the end can.
+        let end = &ends[end_idx];
+        if next_newline(start.text) < end.boundary {
+            return None;
+        }
+
+        offset = end.end;
+        Some((start, end))
+    })
+}
+
+// Find all numbers that are followed by a period or marker and all labels.
+fn scan_items(text: &str) -> Items {
+    let bytes = text.as_bytes();
+    let mut items = Items {
+        numbers: Vec::new(),
+        labels: Vec::new(),
+        newlines: Vec::new(),
+    };
+
+    let mut idx = 0;
+    while idx < bytes.len() {
+        if bytes[idx] == b'\n' {
+            items.newlines.push(idx);
+        }
+
+        if bytes[idx].is_ascii_lowercase() {
+            let start = idx;
+            while idx < bytes.len() && bytes[idx].is_ascii_lowercase() {
+                idx += 1;
+            }
+
+            if bytes.get(idx) != Some(&b'.') {
+                continue;
+            }
+
+            let boundary = match preceding_boundary(text, start) {
+                Some(boundary) => Some(boundary),
+                None if start == 0 => None,
+                None => continue,
+            };
+
+            if let Some(whitespace_len) = following_whitespace_len(text, idx + 1) {
+                items.labels.push(Label {
+                    letters: start..idx,
+                    boundary,
+                    text: idx + 1 + whitespace_len,
+                });
+            }
+
+            continue;
+        }
+
+        if !bytes[idx].is_ascii_digit() {
+            idx += 1;
+            continue;
+        }
+
+        let start = idx;
+        while idx < bytes.len() && bytes[idx].is_ascii_digit() {
+            idx += 1;
+        }
+
+        let marker = match bytes.get(idx) {
+            Some(&marker) if marker == b'.' || marker == b'#' => marker,
+            _ => continue,
+        };
+
+        items.numbers.push(Number {
+            digits: start..idx,
+            boundary: preceding_boundary(text, start),
+            marker,
+            whitespace_len: following_whitespace_len(text, idx + 1),
+        });
+    }
+
+    items
+}
+
+// Get the offset of the character before `offset` if it is a non-word
+// character.
+fn preceding_boundary(text: &str, offset: usize) -> Option<usize> {
+    text[..offset]
+        .chars()
+        .next_back()
+        .filter(|&ch| !is_word_char(ch))
+        .map(|ch| offset - ch.len_utf8())
+}
+
+// Get the length of the character at `offset` if it is whitespace.
+fn following_whitespace_len(text: &str, offset: usize) -> Option<usize> {
+    text[offset..]
+        .chars()
+        .next()
+        .filter(|ch| ch.is_whitespace())
+        .map(char::len_utf8)
+}
+
+// Maximum number of digits of an item number.
+const MAX_NUMBER_LEN: usize = 20;
+
+fn parse_number(digits: &str) -> Option<usize> {
+    if digits.len() > 1 && digits.starts_with('0') {
+        return None;
+    }
+
+    digits.parse().ok()
+}
+
+fn count_digits(mut n: usize) -> usize {
+    let mut len = 1;
+    while n >= 10 {
+        n /= 10;
+        len += 1;
+    }
+    len
+}
+
+// Check whether `label` is a letter or Roman numeral enumeration label.
+pub(crate) fn is_enumeration_label(label: &str) -> bool {
+    LabelKind::Letter.value(label).is_some() || LabelKind::Roman.value(label).is_some()
+}
+
+// Maximum length of a Roman numeral, `mmmdccclxxxviii` (3888).
+const MAX_ROMAN_LEN: usize = 15;
+
+fn roman_value(numeral: &str) -> Option<usize> {
+    if numeral.is_empty() || numeral.len() > MAX_ROMAN_LEN {
+        return None;
+    }
+
+    let mut value: usize = 0;
+    let mut max_digit = 0;
+    for digit in numeral.bytes().rev() {
+        let digit = match digit {
+            b'i' => 1,
+            b'v' => 5,
+            b'x' => 10,
+            b'l' => 50,
+            b'c' => 100,
+            b'd' => 500,
+            b'm' => 1000,
+            _ => return None,
+        };
+
+        if digit < max_digit {
+            // Non-canonical numerals such as `ix` can subtract more than
+            // the value of the digits that follow.
+            value = value.checked_sub(digit)?;
+        } else {
+            value += digit;
+            max_digit = digit;
+        }
+    }
+
+    // Only accept numerals in their canonical form, e.g. `iv` and not `iiii`.
+    if to_roman(value) == numeral {
+        Some(value)
+    } else {
+        None
+    }
+}
+
+fn to_roman(mut n: usize) -> String {
+    const NUMERALS: [(usize, &str); 13] = [
+        (1000, "m"),
+        (900, "cm"),
+        (500, "d"),
+        (400, "cd"),
+        (100, "c"),
+        (90, "xc"),
+        (50, "l"),
+        (40, "xl"),
+        (10, "x"),
+        (9, "ix"),
+        (5, "v"),
+        (4, "iv"),
+        (1, "i"),
+    ];
+
+    let mut numeral = String::new();
+    for &(value, digits) in &NUMERALS {
+        while n >= value {
+            numeral.push_str(digits);
+            n -= value;
+        }
+    }
+    numeral
+}
+
+// Check whether `ch` is a word character, as matched by `\w`.
+fn is_word_char(ch: char) -> bool {
+    lazy_static! {
+        static ref WORD_CHAR: Regex = Regex::new("^\\w$").unwrap();
+    }
+
+    if ch.is_ascii() {
+        ch.is_ascii_alphanumeric() || ch == '_'
+    } else {
+        WORD_CHAR.is_match(ch.encode_utf8(&mut [0; 4]))
+    }
+}
+
+/// Mark the periods of enumerations.
+///
+/// The periods in enumerations such as `1. foo, 2. bar en 3. baz` are
+/// replaced by `#`, so that the tokenizer does not treat them as
+/// sentence ends. Besides numbers, enumerations with lowercase letters
+/// (`a. foo, b. bar`) and lowercase Roman numerals (`i. foo, ii. bar`)
+/// are marked. The markers are replaced by periods again by
+/// [`RemoveEnumerationMarkers`].
+///
+/// [`RemoveEnumerationMarkers`]: crate::RemoveEnumerationMarkers
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AddEnumerationMarkers;
+
+impl TextProcessor for AddEnumerationMarkers {
+    fn name(&self) -> &str {
+        "add_enumeration_markers"
+    }
+
+    fn process(&self, text: &str, dst: &mut String) -> bool {
+        add_enumeration_markers(text, dst, None)
+    }
+
+    fn process_aligned(&self, text: &str, dst: &mut String, alignment: &mut EditAlignment) -> bool {
+        add_enumeration_markers(text, dst, Some(alignment))
+    }
+}
+
+fn add_enumeration_markers(
+    text: &str,
+    dst: &mut String,
+    mut alignment: Option<&mut EditAlignment>,
+) -> bool {
+    let periods = enumeration_periods(text);
+    if periods.is_empty() {
+        return false;
+    }
+
+    dst.clear();
+    let mut last_end = 0;
+    for period in periods {
+        dst.push_str(&text[last_end..period]);
+        dst.push('#');
+        if let Some(alignment) = alignment.as_deref_mut() {
+            alignment.push(period..period + 1, period..period + 1);
+        }
+        last_end = period + 1;
+    }
+    dst.push_str(&text[last_end..]);
+
+    true
 }
 
 #[cfg(test)]
 mod tests {
-    use super::preprocess;
+    use std::borrow::Cow;
+
+    use regex::Regex;
+
+    use crate::pipeline::Pipeline;
+
+    fn preprocess(text: &str) -> String {
+        Pipeline::alpino_preprocessing().process(text)
+    }
+
+    // The original implementation, which applies a regular expression for
+    // every enumeration item.
+    fn add_enumeration_markers_regex(text: &str) -> Cow<'_, str> {
+        let re = Regex::new("(\\s?1)[.](\\s.*?\\W2[.])").unwrap();
+        let mut text = re.replace_all(text, "$1#$2");
+
+        if let text @ Cow::Borrowed(_) = text {
+            return text;
+        }
+
+        let mut prev = 1;
+        let mut next = 2;
+
+        loop {
+            let next_expr = Regex::new(&format!("({}#\\s.*?\\W{})[.](\\s)", prev, next))
+                .expect("Invalid enumeration expression.");
+            let text_after = next_expr.replace_all(&text, "$1#$2");
+
+            if let Cow::Borrowed(_) = text_after {
+                break;
+            }
+
+            text = Cow::Owned(text_after.into_owned());
+            prev += 1;
+            next += 1;
+        }
+
+        text
+    }
+
+    // Generate pseudo-random texts from a small alphabet.
+    fn random_texts(n_texts: usize) -> Vec<String> {
+        let alphabet = [
+            "1", "2", "3", "4", "10", "11", "12", "21", "0", ".", "#", " ", "\n", "\t", "a", "é",
+            ",", "_", "(", "\u{a0}", "\u{300}",
+        ];
+
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+
+        (0..n_texts)
+            .map(|_| {
+                let len = next() % 40;
+                (0..len)
+                    .map(|_| alphabet[next() as usize % alphabet.len()])
+                    .collect()
+            })
+            .collect()
+    }
 
     #[test]
     fn add_enumeration_markers() {
@@ -61,4 +692,131 @@ mod tests {
             "1# boter, 2# kaas en 3# eieren, 1# foo en 2# bar"
         );
     }
+
+    #[test]
+    fn enumeration_markers_edge_cases() {
+        // No enumeration, or an enumeration that does not start at 1.
+        assert_eq!(preprocess("Dit is 1. zin."), "Dit is 1. zin.");
+        assert_eq!(preprocess("2. boter en 3. kaas"), "2. boter en 3. kaas");
+
+        // The first item does not require a word boundary, later items do.
+        assert_eq!(preprocess("Stap 11. en 2. klaar"), "Stap 11# en 2# klaar");
+        assert_eq!(preprocess("1. boter en a2. kaas"), "1. boter en a2. kaas");
+
+        // Items after the second item must be followed by whitespace.
+        assert_eq!(preprocess("1. a, 2. b, 3.c"), "1# a, 2# b, 3.c");
+        assert_eq!(preprocess("1. a, 2.b, 3. c"), "1# a, 2.b, 3. c");
+
+        // Items cannot be separated by a newline, except directly before
+        // the item number.
+        assert_eq!(preprocess("1. a\n2. b\n3. c"), "1# a\n2# b\n3# c");
+        assert_eq!(preprocess("1. a\nb 2. c"), "1. a\nb 2. c");
+        assert_eq!(preprocess("1. a, 2. b\nc 3. d"), "1# a, 2# b\nc 3. d");
+
+        // Overlapping enumerations: the first start is used.
+        assert_eq!(preprocess("1. a 1. b 2. c 2. d"), "1# a 1. b 2# c 2. d");
+
+        // Markers in the input can start items.
+        assert_eq!(preprocess("1. a 2. b 5# c 6. d"), "1# a 2# b 5# c 6. d");
+        assert_eq!(preprocess("1. a 2. b 2# c 3. d"), "1# a 2# b 2# c 3# d");
+
+        // Non-ASCII word characters and whitespace.
+        assert_eq!(preprocess("1.\u{a0}a é2. b"), "1.\u{a0}a é2. b");
+        assert_eq!(preprocess("1.\u{a0}a (2.\u{a0}b"), "1#\u{a0}a (2#\u{a0}b");
+    }
+
+    #[test]
+    fn letter_enumerations() {
+        assert_eq!(
+            preprocess("Ik koop a. boter, b. kaas en c. eieren."),
+            "Ik koop a# boter, b# kaas en c# eieren."
+        );
+        assert_eq!(
+            preprocess("De huurder:\na. betaalt de huur;\nb. houdt de woning schoon."),
+            "De huurder:\na# betaalt de huur;\nb# houdt de woning schoon."
+        );
+
+        // An enumeration starts with `a.` and its labels are separate words.
+        assert_eq!(preprocess("b. boter en c. kaas"), "b. boter en c. kaas");
+        assert_eq!(preprocess("a. boter en sub. kaas"), "a. boter en sub. kaas");
+        assert_eq!(preprocess("a. boter en b.kaas"), "a. boter en b.kaas");
+        assert_eq!(preprocess("Ik heb data. b. c"), "Ik heb data. b. c");
+        assert_eq!(preprocess("a. boter\nen b. kaas"), "a. boter\nen b. kaas");
+    }
+
+    #[test]
+    fn roman_numeral_enumerations() {
+        assert_eq!(
+            preprocess("Ik koop i. boter, ii. kaas, iii. melk en iv. eieren."),
+            "Ik koop i# boter, ii# kaas, iii# melk en iv# eieren."
+        );
+
+        // `i.` is both a letter and a Roman numeral.
+        assert_eq!(
+            preprocess("h. boter, i. kaas en j. melk.\ni. foo en ii. bar"),
+            "h. boter, i. kaas en j. melk.\ni# foo en ii# bar"
+        );
+
+        // Numerals must be in their canonical form.
+        assert_eq!(
+            preprocess("i. foo, ii. bar en iiii. baz"),
+            "i# foo, ii# bar en iiii. baz"
+        );
+        assert_eq!(preprocess("zie iiiiiiiiiiix. foo"), "zie iiiiiiiiiiix. foo");
+
+        let text = (1..=40)
+            .map(|n| format!("{}. artikel", super::to_roman(n)))
+            .collect::<Vec<_>>()
+            .join(", ");
+        assert_eq!(preprocess(&text), text.replace(". ", "# "));
+    }
+
+    #[test]
+    fn roman_numerals() {
+        for n in 1..=3999 {
+            assert_eq!(super::roman_value(&super::to_roman(n)), Some(n));
+        }
+
+        assert_eq!(super::to_roman(1994), "mcmxciv");
+        assert_eq!(super::roman_value("iiii"), None);
+        assert_eq!(super::roman_value("iiiiiiiiiiix"), None);
+        assert_eq!(super::roman_value("vx"), None);
+        assert_eq!(super::roman_value("abc"), None);
+        assert_eq!(super::roman_value(""), None);
+    }
+
+    #[test]
+    fn long_enumerations() {
+        let text = (1..=200)
+            .map(|n| format!("{}. artikel", n))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let marked = (1..=200)
+            .map(|n| format!("{}# artikel", n))
+            .collect::<Vec<_>>()
+            .join(", ");
+        assert_eq!(preprocess(&text), marked);
+    }
+
+    #[test]
+    fn enumeration_markers_match_regex() {
+        for text in random_texts(1_000) {
+            assert_eq!(
+                preprocess(&text),
+                add_enumeration_markers_regex(&text),
+                "Text: {:?}",
+                text
+            );
+        }
+
+        let pipeline = Pipeline::alpino_preprocessing();
+        for text in random_texts(1_000) {
+            let (marked, alignment) = pipeline.process_aligned(&text);
+            assert_eq!(marked, pipeline.process(&text));
+            for edit in alignment.edits() {
+                assert_eq!(&text[edit.source.clone()], ".");
+                assert_eq!(&marked[edit.target.clone()], "#");
+            }
+        }
+    }
 }
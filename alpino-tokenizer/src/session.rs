@@ -0,0 +1,151 @@
+use crate::fst::Walker;
+use crate::tokenized::TokenizedText;
+use crate::{AlpinoTokenizer, FiniteStateTokenizer, TokenizerError};
+
+/// Tokenization session with reusable buffers.
+///
+/// Each call of `Tokenizer::tokenize_text` allocates the output buffer,
+/// the boundary indices and other intermediate buffers. A session keeps
+/// these buffers between calls, so that they are only allocated again
+/// when they need to grow. This is useful when many short paragraphs are
+/// tokenized, e.g. in a worker thread.
+///
+/// A session is constructed with `FiniteStateTokenizer::session` or
+/// `AlpinoTokenizer::session`:
+///
+/// ```
+/// use std::fs::File;
+/// use std::io::BufReader;
+///
+/// use alpino_tokenizer::AlpinoTokenizer;
+///
+/// let read = BufReader::new(File::open("testdata/toy.proto").unwrap());
+/// let tokenizer = AlpinoTokenizer::from_buf_read(read).unwrap();
+///
+/// let mut session = tokenizer.session();
+/// for paragraph in &["Dit is een zin.", "Dit is nog een zin."] {
+///     let tokenized = session.tokenize_text(paragraph).unwrap();
+///     assert_eq!(tokenized.n_sentences(), 1);
+/// }
+/// ```
+pub struct TokenizerSession<'a> {
+    walker: Walker<'a>,
+    alpino: Option<&'a AlpinoTokenizer>,
+    preprocessed: String,
+    raw: String,
+    scratch: String,
+    tokenized: TokenizedText,
+}
+
+impl<'a> TokenizerSession<'a> {
+    fn new(tokenizer: &'a FiniteStateTokenizer, alpino: Option<&'a AlpinoTokenizer>) -> Self {
+        TokenizerSession {
+            walker: Walker::new(tokenizer),
+            alpino,
+            preprocessed: String::new(),
+            raw: String::new(),
+            scratch: String::new(),
+            tokenized: TokenizedText::new(String::new()),
+        }
+    }
+
+    /// Sentence-split and tokenize a paragraph of text.
+    ///
+    /// The returned tokenized text is overwritten by the next call.
+    pub fn tokenize_text(&mut self, text: &str) -> Result<&TokenizedText, TokenizerError> {
+        let walker = &mut self.walker;
+        walker.reset();
+
+        if let Some(alpino) = self.alpino {
+            alpino
+                .preprocessing()
+                .process_into(text, &mut self.scratch, &mut self.preprocessed);
+            self.raw.clear();
+            walker
+                .tokenize_into(self.preprocessed.chars(), &mut self.raw)
+                .map_err(|err| alpino.project_error(text, err))?;
+
+            let input_hashes = alpino.input_hashes(text, &self.preprocessed, &self.raw);
+            let (raw, scratch) = (&self.raw, &mut self.scratch);
+            self.tokenized.refill(|output| {
+                alpino.postprocess_into(raw, &input_hashes, scratch, output);
+                Ok::<_, TokenizerError>(())
+            })?;
+        } else {
+            self.tokenized
+                .refill(|output| walker.tokenize_into(text.chars(), output))?;
+        }
+
+        Ok(&self.tokenized)
+    }
+}
+
+impl FiniteStateTokenizer {
+    /// Start a tokenization session with reusable buffers.
+    pub fn session(&self) -> TokenizerSession<'_> {
+        TokenizerSession::new(self, None)
+    }
+}
+
+impl AlpinoTokenizer {
+    /// Start a tokenization session with reusable buffers.
+    pub fn session(&self) -> TokenizerSession<'_> {
+        TokenizerSession::new(self.inner(), Some(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::BufReader;
+
+    use crate::{AlpinoTokenizer, FiniteStateTokenizer, Tokenizer, TokenizerError};
+
+    static PARAGRAPHS: &[&str] = &[
+        "Dit is een zin. En dit is nog een zin...",
+        "Ik ga -zoals gezegd- naar huis. Hij is in ' top'-vorm.",
+        "1. boter, 2. kaas en 3. eieren",
+        "Ik ken a. c# en b. f#",
+        "",
+        "Kort.",
+    ];
+
+    fn read() -> BufReader<File> {
+        BufReader::new(File::open("testdata/toy.proto").unwrap())
+    }
+
+    #[test]
+    fn session_is_equal_to_tokenize() {
+        let fst = FiniteStateTokenizer::from_buf_read(read()).unwrap();
+        let alpino = AlpinoTokenizer::from_buf_read(read()).unwrap();
+
+        let mut fst_session = fst.session();
+        let mut alpino_session = alpino.session();
+        for paragraph in PARAGRAPHS {
+            assert_eq!(
+                fst_session.tokenize_text(paragraph).unwrap(),
+                &fst.tokenize_text(paragraph).unwrap()
+            );
+            assert_eq!(
+                alpino_session.tokenize_text(paragraph).unwrap(),
+                &alpino.tokenize_text(paragraph).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn session_recovers_after_error() {
+        let alpino = AlpinoTokenizer::from_buf_read(read()).unwrap();
+        let mut session = alpino.session();
+
+        assert!(matches!(
+            session.tokenize_text("Dit is\0een zin."),
+            Err(TokenizerError::NoTransition { offset: 6, .. })
+        ));
+
+        assert_eq!(
+            session.tokenize_text("Dit is een zin.").unwrap(),
+            &alpino.tokenize_text("Dit is een zin.").unwrap()
+        );
+    }
+}
@@ -33,7 +33,7 @@ pub enum SmallString {
 assert_eq_size!(SmallString, (Box<String>, Box<String>));
 
 // We should be able to represent the length of the small string.
-const_assert!(SMALL_STR_LEN <= std::u8::MAX as usize);
+const_assert!(SMALL_STR_LEN <= u8::MAX as usize);
 
 impl Deref for SmallString {
     type Target = str;
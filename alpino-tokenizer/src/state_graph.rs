@@ -0,0 +1,255 @@
+use std::collections::{HashMap, VecDeque};
+
+use thiserror::Error;
+
+use crate::transducer::{Transducer, Transition};
+
+/// Symbol of the transitions of states without outgoing transitions.
+///
+/// States are identified by the offset of their first transition, so
+/// every state needs at least one transition. This symbol is not a
+/// Unicode scalar value and can never be matched.
+pub(crate) const DEAD_SYMBOL: u32 = 0x110000;
+
+/// Transducer represented as a graph of states.
+///
+/// This is an intermediate representation for constructing transducers.
+/// States are identified by their index, the graph is converted to the
+/// sorted transition layout of `Transducer` by `into_transducer`.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct StateGraph {
+    states: Vec<GraphState>,
+}
+
+#[derive(Clone, Debug, Default)]
+struct GraphState {
+    arcs: Vec<Arc>,
+    final_output: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+struct Arc {
+    symbol: u32,
+    output: String,
+    next: usize,
+}
+
+/// Transducer construction errors.
+#[derive(Clone, Debug, Eq, Error, PartialEq)]
+pub enum BuildError {
+    /// The state already has a transition on the symbol.
+    #[error("State {state} has multiple transitions on symbol {symbol}")]
+    DuplicateSymbol { state: usize, symbol: u32 },
+
+    /// The state already has a different final output.
+    #[error("State {state} has multiple final outputs")]
+    ConflictingFinalOutput { state: usize },
+
+    /// The state has transitions on both unknown character symbols.
+    #[error("State {state} has transitions for both queued and other unknown characters")]
+    ConflictingUnknownSymbols { state: usize },
+
+    /// The state was not added to the transducer.
+    #[error("Transducer has no state {state}")]
+    UnknownState { state: usize },
+
+    /// The character is reserved for the sentinel transition or for
+    /// unknown character transitions.
+    #[error("Character {character:?} is reserved")]
+    ReservedCharacter { character: char },
+
+    /// The string of a pair is empty.
+    #[error("Pair with an empty string")]
+    EmptyPair,
+
+    /// The string was already added with a different output.
+    #[error("Pair {input:?} has multiple outputs")]
+    ConflictingPair { input: String },
+
+    /// The start state has both pairs and other transitions.
+    #[error("Start state {state} has both pairs and other transitions or a final output")]
+    PairsOnStartState { state: usize },
+}
+
+impl StateGraph {
+    /// Add a state, returning its identifier.
+    pub fn add_state(&mut self) -> usize {
+        self.states.push(GraphState::default());
+        self.states.len() - 1
+    }
+
+    /// Add a transition.
+    pub fn add_arc(
+        &mut self,
+        state: usize,
+        symbol: u32,
+        output: impl Into<String>,
+        next: usize,
+    ) -> Result<(), BuildError> {
+        self.check_state(state)?;
+        self.check_state(next)?;
+
+        // Symbol 0 is used by the sentinel transition. Moreover, since the
+        // transitions of a state are sorted by symbol, a transition on U+0000
+        // would precede the transition for unknown characters, which the
+        // tokenizer expects to be the first transition of the state.
+        if symbol == 0 {
+            return Err(BuildError::ReservedCharacter { character: '\0' });
+        }
+
+        let arcs = &mut self.states[state].arcs;
+
+        if arcs.iter().any(|arc| arc.symbol == symbol) {
+            return Err(BuildError::DuplicateSymbol { state, symbol });
+        }
+
+        // The tokenizer only considers the first transition of a state for
+        // unknown characters.
+        if (symbol == 1 || symbol == 2) && arcs.iter().any(|arc| arc.symbol == 1 || arc.symbol == 2)
+        {
+            return Err(BuildError::ConflictingUnknownSymbols { state });
+        }
+
+        arcs.push(Arc {
+            symbol,
+            output: output.into(),
+            next,
+        });
+
+        Ok(())
+    }
+
+    /// Check whether a state has outgoing transitions.
+    pub fn has_arcs(&self, state: usize) -> bool {
+        !self.states[state].arcs.is_empty()
+    }
+
+    /// Get the final output of a state, `None` if the state is not final.
+    pub fn final_output(&self, state: usize) -> Option<&str> {
+        self.states[state].final_output.as_deref()
+    }
+
+    /// Make a state final.
+    pub fn set_final(
+        &mut self,
+        state: usize,
+        final_output: impl Into<String>,
+    ) -> Result<(), BuildError> {
+        self.check_state(state)?;
+
+        let final_output = final_output.into();
+        match &self.states[state].final_output {
+            Some(current) if *current != final_output => {
+                Err(BuildError::ConflictingFinalOutput { state })
+            }
+            _ => {
+                self.states[state].final_output = Some(final_output);
+                Ok(())
+            }
+        }
+    }
+
+    fn check_state(&self, state: usize) -> Result<(), BuildError> {
+        if state < self.states.len() {
+            Ok(())
+        } else {
+            Err(BuildError::UnknownState { state })
+        }
+    }
+
+    /// Convert the graph to a transducer with `start` as the start state.
+    ///
+    /// States that are not reachable from the start state are removed.
+    pub fn into_transducer(self, start: usize) -> Transducer {
+        let order = self.reachable(start);
+
+        // Compute the offset of each state. Transitions 0 and 1 are the
+        // sentinel and the transition to the start state.
+        let mut offsets = HashMap::new();
+        let mut offset = 2;
+        for &state in &order {
+            offsets.insert(state, offset as u32);
+            offset += self.states[state].arcs.len().max(1);
+        }
+
+        let mut transitions = Vec::with_capacity(offset);
+        transitions.push(Transition {
+            symbol: 0,
+            is_last_of_state: true,
+            is_final_state: false,
+            next: 0,
+            output: "".into(),
+            final_output: "".into(),
+        });
+        transitions.push(self.transition(0, false, "", start, &offsets));
+
+        for state in order {
+            let mut arcs = self.states[state].arcs.iter().collect::<Vec<_>>();
+            arcs.sort_by_key(|arc| arc.symbol);
+
+            if arcs.is_empty() {
+                transitions.push(Transition {
+                    symbol: DEAD_SYMBOL,
+                    is_last_of_state: true,
+                    is_final_state: false,
+                    next: offsets[&state],
+                    output: "".into(),
+                    final_output: "".into(),
+                });
+            }
+
+            for (idx, arc) in arcs.iter().enumerate() {
+                transitions.push(self.transition(
+                    arc.symbol,
+                    idx + 1 == arcs.len(),
+                    &arc.output,
+                    arc.next,
+                    &offsets,
+                ));
+            }
+        }
+
+        Transducer::from_transitions(transitions)
+    }
+
+    fn transition(
+        &self,
+        symbol: u32,
+        is_last_of_state: bool,
+        output: &str,
+        next: usize,
+        offsets: &HashMap<usize, u32>,
+    ) -> Transition {
+        // Final outputs are stored in the transitions to a state.
+        let final_output = self.states[next].final_output.as_deref();
+        Transition {
+            symbol,
+            is_last_of_state,
+            is_final_state: final_output.is_some(),
+            next: offsets[&next],
+            output: output.into(),
+            final_output: final_output.unwrap_or("").into(),
+        }
+    }
+
+    /// States that are reachable from `start`, in breadth-first order.
+    fn reachable(&self, start: usize) -> Vec<usize> {
+        let mut seen = vec![false; self.states.len()];
+        seen[start] = true;
+        let mut order = Vec::new();
+        let mut agenda = VecDeque::new();
+        agenda.push_back(start);
+
+        while let Some(state) = agenda.pop_front() {
+            order.push(state);
+            for arc in &self.states[state].arcs {
+                if !seen[arc.next] {
+                    seen[arc.next] = true;
+                    agenda.push_back(arc.next);
+                }
+            }
+        }
+
+        order
+    }
+}
@@ -0,0 +1,309 @@
+use std::io::{self, BufRead};
+use std::mem;
+use std::str;
+
+use crate::fst::Walker;
+use crate::{FiniteStateTokenizer, TokenizerError};
+
+/// Iterator over the sentences of a character stream.
+///
+/// Sentences are returned as soon as the transducer emits a newline, so
+/// only the output of the current sentence is buffered.
+pub(crate) struct SentenceStream<'a, I> {
+    walker: Walker<'a>,
+    chars: I,
+    offset: usize,
+    output: String,
+    // Start of the output that was not scanned for newlines yet.
+    unscanned: usize,
+    // All input was read and the final output was emitted.
+    finished: bool,
+    // The last sentence or error was returned.
+    done: bool,
+}
+
+impl<'a, I> SentenceStream<'a, I>
+where
+    I: Iterator<Item = io::Result<char>>,
+{
+    pub(crate) fn new(tokenizer: &'a FiniteStateTokenizer, chars: I) -> Self {
+        SentenceStream {
+            walker: Walker::new(tokenizer),
+            chars,
+            offset: 0,
+            output: String::new(),
+            unscanned: 0,
+            finished: false,
+            done: false,
+        }
+    }
+
+    fn take_sentence(&mut self) -> Option<Vec<String>> {
+        let newline = self.output[self.unscanned..]
+            .find('\n')
+            .map(|idx| self.unscanned + idx);
+
+        match newline {
+            Some(newline) => {
+                let sentence = str_to_sentence(&self.output[..newline]);
+                self.output.drain(..=newline);
+                self.unscanned = 0;
+                Some(sentence)
+            }
+            None => {
+                self.unscanned = self.output.len();
+                None
+            }
+        }
+    }
+}
+
+impl<'a, I> Iterator for SentenceStream<'a, I>
+where
+    I: Iterator<Item = io::Result<char>>,
+{
+    type Item = Result<Vec<String>, TokenizerError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(sentence) = self.take_sentence() {
+                return Some(Ok(sentence));
+            }
+
+            if self.done {
+                return None;
+            }
+
+            if self.finished {
+                // The remaining output is the last sentence.
+                self.done = true;
+                let sentence = str_to_sentence(&self.output);
+                self.output.clear();
+                self.unscanned = 0;
+                return Some(Ok(sentence));
+            }
+
+            match self.chars.next() {
+                Some(Ok(ch)) => {
+                    if let Err(state) = self.walker.step(ch, &mut self.output) {
+                        self.done = true;
+                        self.unscanned = 0;
+                        return Some(Err(TokenizerError::NoTransition {
+                            offset: self.offset,
+                            character: ch,
+                            state,
+                            partial_output: mem::take(&mut self.output),
+                        }));
+                    }
+                    self.offset += 1;
+                }
+                Some(Err(err)) => {
+                    self.done = true;
+                    return Some(Err(TokenizerError::ReadInputError(err)));
+                }
+                None => {
+                    self.walker.finish(&mut self.output);
+                    self.finished = true;
+                }
+            }
+        }
+    }
+}
+
+fn str_to_sentence(sentence: &str) -> Vec<String> {
+    sentence.split(' ').map(ToOwned::to_owned).collect()
+}
+
+/// Iterator over the characters of a UTF-8 encoded reader.
+pub(crate) struct ReadChars<R> {
+    read: R,
+    chunk: String,
+    pos: usize,
+    incomplete: Vec<u8>,
+}
+
+impl<R> ReadChars<R>
+where
+    R: BufRead,
+{
+    pub(crate) fn new(read: R) -> Self {
+        ReadChars {
+            read,
+            chunk: String::new(),
+            pos: 0,
+            incomplete: Vec::new(),
+        }
+    }
+
+    // Decode the next chunk of the reader. Returns `false` when the
+    // reader is exhausted.
+    fn fill_chunk(&mut self) -> io::Result<bool> {
+        self.chunk.clear();
+        self.pos = 0;
+
+        let buf = loop {
+            match self.read.fill_buf() {
+                Ok(buf) => break buf,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            }
+        };
+
+        if buf.is_empty() {
+            if self.incomplete.is_empty() {
+                return Ok(false);
+            }
+
+            self.incomplete.clear();
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "invalid or incomplete UTF-8 sequence at end of input",
+            ));
+        }
+
+        let mut bytes = mem::take(&mut self.incomplete);
+        bytes.extend_from_slice(buf);
+        let consumed = buf.len();
+        self.read.consume(consumed);
+
+        let valid_up_to = match str::from_utf8(&bytes) {
+            Ok(_) => bytes.len(),
+            Err(err) if err.valid_up_to() == 0 && err.error_len().is_some() => {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, err))
+            }
+            // Decode the valid prefix, the remainder is either cut off by
+            // the end of the buffer or invalid. An invalid remainder results
+            // in an error when decoding the next chunk.
+            Err(err) => err.valid_up_to(),
+        };
+
+        self.chunk.push_str(
+            str::from_utf8(&bytes[..valid_up_to]).expect("Prefix was verified to be valid UTF-8"),
+        );
+        self.incomplete.extend_from_slice(&bytes[valid_up_to..]);
+
+        Ok(true)
+    }
+}
+
+impl<R> Iterator for ReadChars<R>
+where
+    R: BufRead,
+{
+    type Item = io::Result<char>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(ch) = self.chunk[self.pos..].chars().next() {
+                self.pos += ch.len_utf8();
+                return Some(Ok(ch));
+            }
+
+            match self.fill_chunk() {
+                Ok(true) => continue,
+                Ok(false) => return None,
+                Err(err) => return Some(Err(err)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::fs::File;
+    use std::io::{BufReader, Cursor};
+
+    use super::ReadChars;
+    use crate::{FiniteStateTokenizer, Tokenizer, TokenizerError};
+
+    fn tokenizer() -> FiniteStateTokenizer {
+        let read = BufReader::new(File::open("testdata/toy.proto").unwrap());
+        FiniteStateTokenizer::from_buf_read(read).unwrap()
+    }
+
+    #[test]
+    fn read_chars() {
+        let text = "Één zin, één 🦀.";
+        let read = BufReader::with_capacity(3, Cursor::new(text.as_bytes()));
+        assert_eq!(
+            ReadChars::new(read).collect::<Result<String, _>>().unwrap(),
+            text
+        );
+    }
+
+    #[test]
+    fn read_chars_invalid_utf8() {
+        let read = BufReader::with_capacity(3, Cursor::new(b"abcd\xffe".to_vec()));
+        let chars = ReadChars::new(read).collect::<Vec<_>>();
+        assert_eq!(
+            chars[..4]
+                .iter()
+                .map(|ch| *ch.as_ref().unwrap())
+                .collect::<String>(),
+            "abcd"
+        );
+        assert!(chars[4].is_err());
+
+        let read = Cursor::new(b"ab\xc3".to_vec());
+        let chars = ReadChars::new(read).collect::<Vec<_>>();
+        assert_eq!(chars.len(), 3);
+        assert!(chars[2].is_err());
+    }
+
+    #[test]
+    fn stream_is_equal_to_tokenize() {
+        let tokenizer = tokenizer();
+        for text in &[
+            "",
+            "Dit is een zin. En dit is nog een zin...",
+            "Één zin, één 🦀. Of toch twee?",
+        ] {
+            let expected = tokenizer.tokenize(text).unwrap();
+            assert_eq!(
+                tokenizer
+                    .tokenize_chars(text.chars())
+                    .collect::<Result<Vec<_>, _>>()
+                    .unwrap(),
+                expected
+            );
+
+            let read = BufReader::with_capacity(2, Cursor::new(text.as_bytes()));
+            assert_eq!(
+                tokenizer
+                    .tokenize_buf_read(read)
+                    .collect::<Result<Vec<_>, _>>()
+                    .unwrap(),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn stream_yields_sentences_incrementally() {
+        let tokenizer = tokenizer();
+        let text = "Dit is een zin. En dit is nog een zin...";
+        let consumed = Cell::new(0);
+        let mut sentences = tokenizer.tokenize_chars(text.chars().inspect(|_| {
+            consumed.set(consumed.get() + 1);
+        }));
+
+        assert_eq!(
+            sentences.next().unwrap().unwrap(),
+            vec!["Dit", "is", "een", "zin", "."]
+        );
+        assert!(consumed.get() < text.chars().count());
+    }
+
+    #[test]
+    fn stream_reports_errors() {
+        let tokenizer = tokenizer();
+        let mut sentences = tokenizer.tokenize_chars("Een zin. Dit is\0een zin.".chars());
+        assert_eq!(sentences.next().unwrap().unwrap(), vec!["Een", "zin", "."]);
+        assert!(matches!(
+            sentences.next(),
+            Some(Err(TokenizerError::NoTransition { offset: 15, .. }))
+        ));
+        assert!(sentences.next().is_none());
+    }
+}
@@ -0,0 +1,309 @@
+use std::iter::FusedIterator;
+use std::ops::Range;
+
+/// Tokenized text.
+///
+/// The tokenized text is stored in a single buffer, where tokens are
+/// separated by a space and sentences by a newline. The token and sentence
+/// boundaries are stored as indices, so that tokens can be retrieved as
+/// `&str` without allocating a `String` per token.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TokenizedText {
+    text: String,
+
+    /// Byte offset of the end of each token.
+    token_ends: Vec<usize>,
+
+    /// Index of the end of each sentence in `token_ends` (exclusive).
+    sentence_ends: Vec<usize>,
+}
+
+impl TokenizedText {
+    /// Construct tokenized text from tokenizer output.
+    ///
+    /// In the tokenizer output, tokens are separated by a space and
+    /// sentences by a newline.
+    pub fn new(text: String) -> Self {
+        let mut tokenized = TokenizedText {
+            text,
+            token_ends: Vec::new(),
+            sentence_ends: Vec::new(),
+        };
+        tokenized.index();
+        tokenized
+    }
+
+    /// Replace the text by the output of `write`, reusing the buffers.
+    pub(crate) fn refill<F, E>(&mut self, write: F) -> Result<(), E>
+    where
+        F: FnOnce(&mut String) -> Result<(), E>,
+    {
+        self.text.clear();
+        let result = write(&mut self.text);
+        self.index();
+        result
+    }
+
+    fn index(&mut self) {
+        self.token_ends.clear();
+        self.sentence_ends.clear();
+
+        for (idx, byte) in self.text.bytes().enumerate() {
+            match byte {
+                b' ' => self.token_ends.push(idx),
+                b'\n' => {
+                    self.token_ends.push(idx);
+                    self.sentence_ends.push(self.token_ends.len());
+                }
+                _ => (),
+            }
+        }
+
+        self.token_ends.push(self.text.len());
+        self.sentence_ends.push(self.token_ends.len());
+    }
+
+    /// Get the tokenizer output.
+    ///
+    /// Tokens are separated by a space and sentences by a newline.
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// Get the tokenizer output, consuming the tokenized text.
+    pub fn into_string(self) -> String {
+        self.text
+    }
+
+    /// Get the number of sentences.
+    pub fn n_sentences(&self) -> usize {
+        self.sentence_ends.len()
+    }
+
+    /// Get the number of tokens.
+    pub fn n_tokens(&self) -> usize {
+        self.token_ends.len()
+    }
+
+    /// Get a sentence.
+    pub fn sentence(&self, idx: usize) -> Option<TokenizedSentence<'_>> {
+        let end = *self.sentence_ends.get(idx)?;
+        let start = match idx {
+            0 => 0,
+            _ => self.sentence_ends[idx - 1],
+        };
+
+        Some(TokenizedSentence {
+            text: self,
+            tokens: start..end,
+        })
+    }
+
+    /// Get an iterator over the sentences.
+    pub fn sentences(&self) -> Sentences<'_> {
+        Sentences {
+            text: self,
+            sentences: 0..self.n_sentences(),
+        }
+    }
+
+    /// Get an iterator over all tokens.
+    pub fn tokens(&self) -> Tokens<'_> {
+        Tokens {
+            text: self,
+            tokens: 0..self.n_tokens(),
+        }
+    }
+
+    fn token(&self, idx: usize) -> &str {
+        &self.text[self.token_range(idx)]
+    }
+
+    /// Byte range of a token in the tokenizer output.
+    fn token_range(&self, idx: usize) -> Range<usize> {
+        let start = match idx {
+            0 => 0,
+            _ => self.token_ends[idx - 1] + 1,
+        };
+
+        start..self.token_ends[idx]
+    }
+
+    /// Convert to a vector of sentences, with a `String` per token.
+    pub fn to_vec(&self) -> Vec<Vec<String>> {
+        self.sentences()
+            .map(|sent| sent.tokens().map(ToOwned::to_owned).collect())
+            .collect()
+    }
+}
+
+impl From<TokenizedText> for Vec<Vec<String>> {
+    fn from(tokenized: TokenizedText) -> Self {
+        tokenized.to_vec()
+    }
+}
+
+/// Sentence of tokenized text.
+#[derive(Clone, Debug)]
+pub struct TokenizedSentence<'a> {
+    text: &'a TokenizedText,
+    tokens: Range<usize>,
+}
+
+impl<'a> TokenizedSentence<'a> {
+    /// Get the tokenizer output for this sentence.
+    ///
+    /// Tokens are separated by a space.
+    pub fn as_str(&self) -> &'a str {
+        let start = match self.tokens.start {
+            0 => 0,
+            idx => self.text.token_ends[idx - 1] + 1,
+        };
+
+        &self.text.text[start..self.text.token_ends[self.tokens.end - 1]]
+    }
+
+    /// Get the number of tokens.
+    pub fn len(&self) -> usize {
+        self.tokens.len()
+    }
+
+    /// Check whether the sentence is empty.
+    ///
+    /// Sentences always contain at least one, possibly empty, token.
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    /// Get a token.
+    pub fn token(&self, idx: usize) -> Option<&'a str> {
+        if idx < self.len() {
+            Some(self.text.token(self.tokens.start + idx))
+        } else {
+            None
+        }
+    }
+
+    /// Get an iterator over the tokens.
+    pub fn tokens(&self) -> Tokens<'a> {
+        Tokens {
+            text: self.text,
+            tokens: self.tokens.clone(),
+        }
+    }
+
+    /// Get an iterator over the tokens with their byte ranges in the
+    /// tokenizer output.
+    pub(crate) fn token_ranges(&self) -> impl Iterator<Item = (&'a str, Range<usize>)> + 'a {
+        let text = self.text;
+        self.tokens
+            .clone()
+            .map(move |idx| (text.token(idx), text.token_range(idx)))
+    }
+}
+
+/// Iterator over the sentences of tokenized text.
+#[derive(Clone, Debug)]
+pub struct Sentences<'a> {
+    text: &'a TokenizedText,
+    sentences: Range<usize>,
+}
+
+impl<'a> Iterator for Sentences<'a> {
+    type Item = TokenizedSentence<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let idx = self.sentences.next()?;
+        self.text.sentence(idx)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.sentences.size_hint()
+    }
+}
+
+impl<'a> DoubleEndedIterator for Sentences<'a> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let idx = self.sentences.next_back()?;
+        self.text.sentence(idx)
+    }
+}
+
+impl<'a> ExactSizeIterator for Sentences<'a> {}
+
+impl<'a> FusedIterator for Sentences<'a> {}
+
+/// Iterator over tokens.
+#[derive(Clone, Debug)]
+pub struct Tokens<'a> {
+    text: &'a TokenizedText,
+    tokens: Range<usize>,
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let idx = self.tokens.next()?;
+        Some(self.text.token(idx))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.tokens.size_hint()
+    }
+}
+
+impl<'a> DoubleEndedIterator for Tokens<'a> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let idx = self.tokens.next_back()?;
+        Some(self.text.token(idx))
+    }
+}
+
+impl<'a> ExactSizeIterator for Tokens<'a> {}
+
+impl<'a> FusedIterator for Tokens<'a> {}
+
+#[cfg(test)]
+mod tests {
+    use super::TokenizedText;
+    use crate::util::str_to_tokens;
+
+    #[test]
+    fn iterates_sentences_and_tokens() {
+        let tokenized = TokenizedText::new("Dit is een zin .\nNog een zin .".to_owned());
+        assert_eq!(tokenized.n_sentences(), 2);
+        assert_eq!(tokenized.n_tokens(), 9);
+
+        let sentences = tokenized
+            .sentences()
+            .map(|sent| sent.tokens().collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            sentences,
+            vec![
+                vec!["Dit", "is", "een", "zin", "."],
+                vec!["Nog", "een", "zin", "."]
+            ]
+        );
+
+        let second = tokenized.sentence(1).unwrap();
+        assert_eq!(second.as_str(), "Nog een zin .");
+        assert_eq!(second.len(), 4);
+        assert_eq!(second.token(1), Some("een"));
+        assert_eq!(second.token(4), None);
+        assert!(tokenized.sentence(2).is_none());
+
+        assert_eq!(tokenized.tokens().next_back(), Some("."));
+    }
+
+    #[test]
+    fn conversion_is_equal_to_str_to_tokens() {
+        for text in ["", "a", "a b\nc", "a  b\n\nc ", "é ü\nß"] {
+            assert_eq!(
+                Vec::<Vec<String>>::from(TokenizedText::new(text.to_owned())),
+                str_to_tokens(text)
+            );
+        }
+    }
+}
@@ -2,20 +2,117 @@ use std::io;
 
 use thiserror::Error;
 
+use crate::batch::map_parallel;
+use crate::offsets::{align, Sentence};
+use crate::tokenized::TokenizedText;
+use crate::validate::ValidationError;
+
 /// Tokenizer errors.
 #[derive(Debug, Error)]
 pub enum TokenizerError {
-    #[error("Cannot read tokenizer protobuf: {0}")]
+    #[error("Cannot read tokenizer: {0}")]
     RadError(#[from] io::Error),
 
     #[error("Cannot deserialize tokenizer protobuf: {0}")]
     ProtobufDecodeError(#[from] prost::DecodeError),
+
+    #[error("Invalid compact transducer: {0}")]
+    CompactFormatError(String),
+
+    #[error("Invalid transducer: {0}")]
+    ValidationError(#[from] ValidationError),
+
+    #[error("Cannot import transducer, line {line}: {message}")]
+    ImportError { line: usize, message: String },
+
+    #[error("Transducer has no state {0}")]
+    NoSuchState(usize),
+
+    #[error("Transitions to state {0} have different final outputs")]
+    ConflictingFinalOutput(usize),
+
+    #[error("Cannot write transducer: {0}")]
+    WriteError(io::Error),
+
+    #[error("Cannot read tokenizer input: {0}")]
+    ReadInputError(io::Error),
+
+    #[error(
+        "Cannot tokenize character {character:?} at offset {offset} in transducer state {state}"
+    )]
+    NoTransition {
+        /// Character offset of the character in the input.
+        offset: usize,
+
+        /// The character that is not accepted by the transducer.
+        character: char,
+
+        /// The transducer state in which no transition was found.
+        state: usize,
+
+        /// Output of the transducer up to the offending character.
+        partial_output: String,
+    },
 }
 
 /// Tokenizer trait type.
 pub trait Tokenizer {
     /// Sentence-split and tokenize a paragraph of text.
     ///
-    /// The paragraph should be on a single line.
-    fn tokenize(&self, text: &str) -> Option<Vec<Vec<String>>>;
+    /// The paragraph should be on a single line. The tokens are returned in
+    /// a single buffer, see [`TokenizedText`].
+    fn tokenize_text(&self, text: &str) -> Result<TokenizedText, TokenizerError>;
+
+    /// Sentence-split and tokenize a paragraph of text.
+    ///
+    /// The paragraph should be on a single line. This method allocates a
+    /// `String` for every token, use `tokenize_text` to avoid these
+    /// allocations.
+    fn tokenize(&self, text: &str) -> Result<Vec<Vec<String>>, TokenizerError> {
+        self.tokenize_text(text).map(Into::into)
+    }
+
+    /// Sentence-split and tokenize a paragraph of text with offsets.
+    ///
+    /// This method is similar to `tokenize`, but also returns the span of
+    /// every sentence and token in `text`. The spans are computed by aligning
+    /// the tokenizer output with `text`, which relies on the tokenizer only
+    /// adding or removing whitespace and invisible characters.
+    fn tokenize_with_offsets(&self, text: &str) -> Result<Vec<Sentence>, TokenizerError> {
+        self.tokenize_text(text)
+            .map(|tokenized| align(text, &tokenized))
+    }
+
+    /// Sentence-split a paragraph of text.
+    ///
+    /// The sentences are returned as substrings of `text`, so they retain
+    /// the original characters and spacing. Whitespace between sentences
+    /// is not part of the sentences.
+    fn split_sentences<'a>(&self, text: &'a str) -> Result<Vec<&'a str>, TokenizerError> {
+        Ok(self
+            .tokenize_with_offsets(text)?
+            .iter()
+            .map(|sent| &text[sent.span().bytes()])
+            .collect())
+    }
+
+    /// Sentence-split and tokenize paragraphs in parallel.
+    ///
+    /// The paragraphs are tokenized with `n_threads` threads that share
+    /// the tokenizer. If `n_threads` is zero, the number of threads is
+    /// set to the available parallelism. The results are returned in the
+    /// same order as `paragraphs`.
+    fn tokenize_batch<S>(
+        &self,
+        paragraphs: &[S],
+        n_threads: usize,
+    ) -> Vec<Result<TokenizedText, TokenizerError>>
+    where
+        Self: Sized + Sync,
+        S: AsRef<str> + Sync,
+    {
+        map_parallel(paragraphs, n_threads, |paragraph| {
+            self.tokenize_text(paragraph.as_ref())
+        })
+    }
 }
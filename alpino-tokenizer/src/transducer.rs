@@ -0,0 +1,338 @@
+use std::io::Write;
+use std::ops::Range;
+
+use prost::Message as _;
+use prost_derive::Message;
+
+use crate::compact::CompactTransducer;
+use crate::small_string::SmallString;
+use crate::TokenizerError;
+
+/// Finite state transducer.
+///
+/// Transitions are stored in a single array, the transitions of a state
+/// are stored consecutively. States are identified by the offset of their
+/// first transition. The transducer can either use owned transitions or
+/// the transitions of a compact transducer.
+pub(crate) enum Transducer {
+    Owned {
+        transitions: Vec<Transition>,
+        index: TransitionIndex,
+    },
+    Compact(CompactTransducer),
+}
+
+impl Transducer {
+    pub fn from_transitions(transitions: Vec<Transition>) -> Self {
+        let index = TransitionIndex::new(&transitions);
+        Transducer::Owned { transitions, index }
+    }
+
+    /// Get the number of transitions.
+    pub fn len(&self) -> usize {
+        match self {
+            Transducer::Owned { transitions, .. } => transitions.len(),
+            Transducer::Compact(compact) => compact.len(),
+        }
+    }
+
+    /// Get the transitions of a state.
+    ///
+    /// The transitions of a state end with the first transition that is
+    /// marked as the last transition of a state, or at the end of the
+    /// transducer.
+    pub fn state_transitions(&self, state: usize) -> Range<usize> {
+        let mut end = state;
+        while end < self.len() {
+            end += 1;
+            if self.is_last_of_state(end - 1) {
+                break;
+            }
+        }
+
+        state..end
+    }
+
+    pub fn symbol(&self, idx: usize) -> u32 {
+        match self {
+            Transducer::Owned { transitions, .. } => transitions[idx].symbol,
+            Transducer::Compact(compact) => compact.symbol(idx),
+        }
+    }
+
+    pub fn is_last_of_state(&self, idx: usize) -> bool {
+        match self {
+            Transducer::Owned { transitions, .. } => transitions[idx].is_last_of_state,
+            Transducer::Compact(compact) => compact.is_last_of_state(idx),
+        }
+    }
+
+    pub fn is_final_state(&self, idx: usize) -> bool {
+        match self {
+            Transducer::Owned { transitions, .. } => transitions[idx].is_final_state,
+            Transducer::Compact(compact) => compact.is_final_state(idx),
+        }
+    }
+
+    pub fn next(&self, idx: usize) -> u32 {
+        match self {
+            Transducer::Owned { transitions, .. } => transitions[idx].next,
+            Transducer::Compact(compact) => compact.next(idx),
+        }
+    }
+
+    pub fn output(&self, idx: usize) -> &str {
+        match self {
+            Transducer::Owned { transitions, .. } => &transitions[idx].output,
+            Transducer::Compact(compact) => compact.output(idx),
+        }
+    }
+
+    pub fn final_output(&self, idx: usize) -> &str {
+        match self {
+            Transducer::Owned { transitions, .. } => &transitions[idx].final_output,
+            Transducer::Compact(compact) => compact.final_output(idx),
+        }
+    }
+
+    /// Get the symbols of the transitions, sorted and without duplicates.
+    ///
+    /// The alphabet of a compact transducer is stored in the transducer,
+    /// so that it does not have to be computed when the transducer is
+    /// loaded.
+    pub fn alphabet(&self) -> Vec<u32> {
+        match self {
+            Transducer::Owned { transitions, .. } => {
+                let mut alphabet = transitions
+                    .iter()
+                    .map(|transition| transition.symbol)
+                    .collect::<Vec<_>>();
+                alphabet.sort_unstable();
+                alphabet.dedup();
+                alphabet
+            }
+            Transducer::Compact(compact) => compact.alphabet().collect(),
+        }
+    }
+
+    /// Find the offset of the transition for `symbol` in the state
+    /// starting at `state`.
+    pub fn find_transition(&self, state: usize, symbol: u32) -> Option<usize> {
+        match self {
+            Transducer::Owned { transitions, index } => index.find(transitions, state, symbol),
+            Transducer::Compact(compact) => compact.find_transition(state, symbol),
+        }
+    }
+
+    /// Find the offset of the transition for `symbol` in the state
+    /// starting at `state` by scanning its transitions linearly.
+    ///
+    /// This is how transitions were found before they were indexed, it
+    /// is kept as a baseline for the benchmarks.
+    pub fn find_transition_linear(&self, state: usize, symbol: u32) -> Option<usize> {
+        let mut transition = state;
+        while !self.is_last_of_state(transition)
+            && transition + 1 < self.len()
+            && symbol > self.symbol(transition)
+        {
+            transition += 1;
+        }
+
+        if self.symbol(transition) == symbol {
+            Some(transition)
+        } else {
+            None
+        }
+    }
+}
+
+pub(crate) const NO_ASCII_TABLE: u32 = u32::MAX;
+
+/// Index for finding the transitions of a state.
+///
+/// Transitions on ASCII symbols are looked up in a dense table per
+/// state. Other transitions are found using binary search.
+pub(crate) struct TransitionIndex {
+    /// For each transition, the offset after the last transition of
+    /// its state.
+    pub run_ends: Vec<u32>,
+
+    /// For each transition that starts a state, the index of the state's
+    /// ASCII table.
+    pub ascii_table_indices: Vec<u32>,
+
+    /// Per state, the transition offset relative to the state for each
+    /// ASCII symbol plus one, zero if there is no transition.
+    pub ascii_tables: Vec<[u16; 128]>,
+}
+
+impl TransitionIndex {
+    pub fn new(transitions: &[Transition]) -> Self {
+        let mut run_ends = vec![0; transitions.len()];
+        let mut run_end = transitions.len();
+        for (idx, transition) in transitions.iter().enumerate().rev() {
+            if transition.is_last_of_state {
+                run_end = idx + 1;
+            }
+            run_ends[idx] = run_end as u32;
+        }
+
+        // States are identified by the offset of their first transition,
+        // construct an ASCII table for every state that can be reached.
+        let mut states = transitions
+            .iter()
+            .map(|transition| transition.next as usize)
+            .filter(|&state| state < transitions.len())
+            .collect::<Vec<_>>();
+        states.sort_unstable();
+        states.dedup();
+
+        let mut ascii_table_indices = vec![NO_ASCII_TABLE; transitions.len()];
+        let mut ascii_tables = Vec::with_capacity(states.len());
+        for state in states {
+            let run_end = run_ends[state] as usize;
+
+            // Fall back to binary search for very large states.
+            if run_end - state >= u16::MAX as usize {
+                continue;
+            }
+
+            let mut table = [0u16; 128];
+            for (idx, transition) in transitions[state..run_end].iter().enumerate() {
+                if transition.symbol < 128 && table[transition.symbol as usize] == 0 {
+                    table[transition.symbol as usize] = idx as u16 + 1;
+                }
+            }
+
+            ascii_table_indices[state] = ascii_tables.len() as u32;
+            ascii_tables.push(table);
+        }
+
+        TransitionIndex {
+            run_ends,
+            ascii_table_indices,
+            ascii_tables,
+        }
+    }
+
+    /// Find the offset of the transition for `symbol` in the state
+    /// starting at `state`.
+    pub fn find(&self, transitions: &[Transition], state: usize, symbol: u32) -> Option<usize> {
+        let run_end = self.run_ends[state] as usize;
+
+        let table_index = self.ascii_table_indices[state];
+        if symbol < 128 && table_index != NO_ASCII_TABLE {
+            return match self.ascii_tables[table_index as usize][symbol as usize] {
+                0 => None,
+                idx => Some(state + idx as usize - 1),
+            };
+        }
+
+        transitions[state..run_end]
+            .binary_search_by_key(&symbol, |transition| transition.symbol)
+            .ok()
+            .map(|idx| state + idx)
+    }
+}
+
+/// Protobuf transition.
+///
+/// This data type should only be used during deserialization. Actual
+/// transitions are better represented using `Transition`, which performs
+/// the small string optimization.
+#[derive(Clone, PartialEq, Message)]
+pub(crate) struct TransitionProto {
+    #[prost(uint32, tag = "1")]
+    pub symbol: u32,
+
+    #[prost(bool, tag = "2")]
+    pub is_last_of_state: bool,
+
+    #[prost(bool, tag = "3")]
+    pub is_final_state: bool,
+
+    #[prost(uint32, tag = "4")]
+    pub next: u32,
+
+    #[prost(string, tag = "5")]
+    pub output: String,
+
+    #[prost(string, tag = "6")]
+    pub final_output: String,
+}
+
+/// Write a transducer as length-delimited `TransitionProto` records.
+pub(crate) fn write_proto<W>(transducer: &Transducer, mut write: W) -> Result<(), TokenizerError>
+where
+    W: Write,
+{
+    let mut data = Vec::new();
+    for idx in 0..transducer.len() {
+        let transition = TransitionProto {
+            symbol: transducer.symbol(idx),
+            is_last_of_state: transducer.is_last_of_state(idx),
+            is_final_state: transducer.is_final_state(idx),
+            next: transducer.next(idx),
+            output: transducer.output(idx).to_owned(),
+            final_output: transducer.final_output(idx).to_owned(),
+        };
+
+        data.clear();
+        transition
+            .encode_length_delimited(&mut data)
+            .expect("Vec<u8> has insufficient capacity");
+        write.write_all(&data).map_err(TokenizerError::WriteError)?;
+    }
+
+    Ok(())
+}
+
+/// Transition of a finite state transducer.
+pub(crate) struct Transition {
+    pub symbol: u32,
+
+    pub is_last_of_state: bool,
+
+    pub is_final_state: bool,
+
+    pub next: u32,
+
+    pub output: SmallString,
+
+    pub final_output: SmallString,
+}
+
+#[cfg(test)]
+impl Transition {
+    pub fn new(symbol: u32, is_last_of_state: bool, next: u32, output: &str) -> Self {
+        Transition {
+            symbol,
+            is_last_of_state,
+            is_final_state: false,
+            next,
+            output: output.into(),
+            final_output: "".into(),
+        }
+    }
+
+    pub fn with_final_output(self, final_output: &str) -> Self {
+        Transition {
+            is_final_state: true,
+            final_output: final_output.into(),
+            ..self
+        }
+    }
+}
+
+impl From<TransitionProto> for Transition {
+    fn from(trans: TransitionProto) -> Self {
+        Self {
+            symbol: trans.symbol,
+            is_last_of_state: trans.is_last_of_state,
+            is_final_state: trans.is_final_state,
+            next: trans.next,
+            output: trans.output.into(),
+            final_output: trans.final_output.into(),
+        }
+    }
+}
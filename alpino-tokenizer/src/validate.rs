@@ -0,0 +1,338 @@
+use std::collections::{HashSet, VecDeque};
+
+use thiserror::Error;
+
+use crate::transducer::Transducer;
+
+// Paths on which more unknown characters are queued are considered to
+// be unbounded.
+const MAX_UNKNOWN_QUEUE_LEN: usize = 256;
+
+/// Transducer validation errors.
+#[derive(Clone, Debug, Eq, Error, PartialEq)]
+pub enum ValidationError {
+    /// The transducer lacks the sentinel and initial transitions.
+    #[error("Transducer has {0} transitions, at least 2 are required")]
+    TooFewTransitions(usize),
+
+    /// A transition points to a state beyond the last transition.
+    #[error(
+        "Transition {transition} points to state {next}, but the transducer has {len} transitions"
+    )]
+    NextOutOfRange {
+        transition: usize,
+        next: usize,
+        len: usize,
+    },
+
+    /// The transitions of a state run into the transitions of the next state.
+    #[error("Transitions of state {state} are not terminated by a last transition before state {next_state}")]
+    UnterminatedState { state: usize, next_state: usize },
+
+    /// The transitions of a state are not sorted by symbol.
+    #[error("Symbols of state {state} are not sorted at transition {transition}")]
+    UnsortedSymbols { state: usize, transition: usize },
+
+    /// A (final) output has more unknown character placeholders than
+    /// there are unknown characters in the queue.
+    #[error("Transition {transition} outputs more unknown characters than were read")]
+    UnknownQueueUnderflow {
+        transition: usize,
+        final_output: bool,
+    },
+
+    /// A path ends in a final state without outputting all unknown
+    /// characters.
+    #[error("Unknown characters read before final transition {transition} are never output")]
+    UnknownQueueNotEmpty { transition: usize },
+
+    /// The unknown character queue grows without bounds on a path.
+    #[error("Transition {transition} is on a path that reads unknown characters without outputting them")]
+    UnknownQueueUnbounded { transition: usize },
+}
+
+/// Validate a transducer.
+///
+/// The validation first checks that the transducer is structurally valid:
+/// `next` offsets must be in range and the transitions of every state must
+/// be sorted by symbol and end with a last transition before the next state
+/// starts. The transitions of the state at the end of the transducer may
+/// also be terminated by the end of the transducer. Then it checks that
+/// the unknown character placeholders balance along every path: every
+/// placeholder in the output should correspond to an unknown character that
+/// was read and every unknown character should be output.
+pub(crate) fn validate(transducer: &Transducer) -> Result<(), ValidationError> {
+    let len = transducer.len();
+    if len < 2 {
+        return Err(ValidationError::TooFewTransitions(len));
+    }
+
+    let mut states = Vec::new();
+    for transition in 0..len {
+        let next = transducer.next(transition) as usize;
+        if next >= len {
+            return Err(ValidationError::NextOutOfRange {
+                transition,
+                next,
+                len,
+            });
+        }
+        states.push(next);
+    }
+
+    states.sort_unstable();
+    states.dedup();
+
+    for (idx, &state) in states.iter().enumerate() {
+        let next_state = states.get(idx + 1).copied();
+        validate_state(transducer, state, next_state)?;
+    }
+
+    validate_unknown_queue(transducer)
+}
+
+fn validate_state(
+    transducer: &Transducer,
+    state: usize,
+    next_state: Option<usize>,
+) -> Result<(), ValidationError> {
+    let end = next_state.unwrap_or_else(|| transducer.len());
+    for transition in state..end {
+        if transition > state && transducer.symbol(transition - 1) >= transducer.symbol(transition)
+        {
+            return Err(ValidationError::UnsortedSymbols { state, transition });
+        }
+
+        if transducer.is_last_of_state(transition) {
+            return Ok(());
+        }
+    }
+
+    match next_state {
+        Some(next_state) => Err(ValidationError::UnterminatedState { state, next_state }),
+        None => Ok(()),
+    }
+}
+
+// Follow all paths through the transducer, tracking the length of the
+// unknown character queue.
+fn validate_unknown_queue(transducer: &Transducer) -> Result<(), ValidationError> {
+    let initial = (transducer.next(1) as usize, 0);
+    check_final_output(transducer, 1, 0)?;
+
+    let mut seen = HashSet::new();
+    seen.insert(initial);
+    let mut agenda = VecDeque::new();
+    agenda.push_back(initial);
+
+    while let Some((state, queue_len)) = agenda.pop_front() {
+        let mut transition = state;
+        loop {
+            let symbol = transducer.symbol(transition);
+
+            // Transitions for unknown characters can only be used when they
+            // are the first transition of the state. An unknown character is
+            // only queued by transitions with symbol 2.
+            if transition == state || (symbol != 1 && symbol != 2) {
+                let pushed = usize::from(transition == state && symbol == 2);
+                let popped = count_unknown(transducer.output(transition));
+                let queue_len = (queue_len + pushed).checked_sub(popped).ok_or(
+                    ValidationError::UnknownQueueUnderflow {
+                        transition,
+                        final_output: false,
+                    },
+                )?;
+
+                check_final_output(transducer, transition, queue_len)?;
+
+                if queue_len > MAX_UNKNOWN_QUEUE_LEN {
+                    return Err(ValidationError::UnknownQueueUnbounded { transition });
+                }
+
+                let next = (transducer.next(transition) as usize, queue_len);
+                if seen.insert(next) {
+                    agenda.push_back(next);
+                }
+            }
+
+            if transducer.is_last_of_state(transition) || transition + 1 == transducer.len() {
+                break;
+            }
+
+            transition += 1;
+        }
+    }
+
+    Ok(())
+}
+
+fn check_final_output(
+    transducer: &Transducer,
+    transition: usize,
+    queue_len: usize,
+) -> Result<(), ValidationError> {
+    let popped = count_unknown(transducer.final_output(transition));
+    match popped.cmp(&queue_len) {
+        std::cmp::Ordering::Greater => Err(ValidationError::UnknownQueueUnderflow {
+            transition,
+            final_output: true,
+        }),
+        // Only paths that end in a final state have to output all
+        // unknown characters.
+        std::cmp::Ordering::Less if transducer.is_final_state(transition) => {
+            Err(ValidationError::UnknownQueueNotEmpty { transition })
+        }
+        _ => Ok(()),
+    }
+}
+
+fn count_unknown(output: &str) -> usize {
+    output.chars().filter(|&c| c == char::from(2)).count()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::BufReader;
+
+    use super::{validate, ValidationError};
+    use crate::transducer::{Transducer, Transition};
+    use crate::FiniteStateTokenizer;
+
+    fn transition(symbol: u32, is_last: bool, next: u32, output: &str) -> Transition {
+        Transition::new(symbol, is_last, next, output)
+    }
+
+    fn final_transition(symbol: u32, next: u32, output: &str, final_output: &str) -> Transition {
+        Transition::new(symbol, true, next, output).with_final_output(final_output)
+    }
+
+    // Sentinel, initial transition, and an initial state that copies
+    // `a` and unknown characters.
+    fn transitions() -> Vec<Transition> {
+        vec![
+            transition(0, true, 0, ""),
+            final_transition(0, 2, "", ""),
+            transition(2, false, 2, "\u{2}"),
+            final_transition('a' as u32, 2, "a", ""),
+        ]
+    }
+
+    fn check(transitions: Vec<Transition>) -> Result<(), ValidationError> {
+        validate(&Transducer::from_transitions(transitions))
+    }
+
+    #[test]
+    fn toy_transducer_is_valid() {
+        let read = BufReader::new(File::open("testdata/toy.proto").unwrap());
+        let tokenizer = FiniteStateTokenizer::from_buf_read(read).unwrap();
+        assert_eq!(tokenizer.validate(), Ok(()));
+
+        let mut data = Vec::new();
+        tokenizer.write_compact(&mut data).unwrap();
+        let compact = FiniteStateTokenizer::from_compact(data).unwrap();
+        assert_eq!(compact.validate(), Ok(()));
+    }
+
+    #[test]
+    fn valid_transducer() {
+        assert_eq!(check(transitions()), Ok(()));
+    }
+
+    #[test]
+    fn too_few_transitions() {
+        assert_eq!(
+            check(vec![transition(0, true, 0, "")]),
+            Err(ValidationError::TooFewTransitions(1))
+        );
+    }
+
+    #[test]
+    fn next_out_of_range() {
+        let mut transitions = transitions();
+        transitions[3].next = 4;
+        assert_eq!(
+            check(transitions),
+            Err(ValidationError::NextOutOfRange {
+                transition: 3,
+                next: 4,
+                len: 4
+            })
+        );
+    }
+
+    #[test]
+    fn unsorted_symbols() {
+        let mut transitions = transitions();
+        transitions[3] = transition('b' as u32, false, 2, "b");
+        transitions.push(final_transition('a' as u32, 2, "a", ""));
+        assert_eq!(
+            check(transitions),
+            Err(ValidationError::UnsortedSymbols {
+                state: 2,
+                transition: 4
+            })
+        );
+    }
+
+    #[test]
+    fn unterminated_state() {
+        let mut transitions = transitions();
+        transitions[3].is_last_of_state = false;
+        transitions[3].next = 4;
+        transitions.push(final_transition('b' as u32, 2, "b", ""));
+        assert_eq!(
+            check(transitions),
+            Err(ValidationError::UnterminatedState {
+                state: 2,
+                next_state: 4
+            })
+        );
+    }
+
+    #[test]
+    fn unknown_queue_underflow() {
+        let mut underflow = transitions();
+        underflow[3].output = "a\u{2}".into();
+        assert_eq!(
+            check(underflow),
+            Err(ValidationError::UnknownQueueUnderflow {
+                transition: 3,
+                final_output: false
+            })
+        );
+
+        let mut final_underflow = transitions();
+        final_underflow[3].final_output = "\u{2}".into();
+        assert_eq!(
+            check(final_underflow),
+            Err(ValidationError::UnknownQueueUnderflow {
+                transition: 3,
+                final_output: true
+            })
+        );
+    }
+
+    #[test]
+    fn unknown_queue_not_empty() {
+        let mut transitions = transitions();
+        transitions[2].is_final_state = true;
+        transitions[2].output = "".into();
+        transitions[3].output = "a\u{2}".into();
+        assert_eq!(
+            check(transitions),
+            Err(ValidationError::UnknownQueueNotEmpty { transition: 2 })
+        );
+    }
+
+    #[test]
+    fn unknown_queue_unbounded() {
+        let mut transitions = transitions();
+        transitions[2].output = "".into();
+        transitions[3].is_final_state = false;
+        assert_eq!(
+            check(transitions),
+            Err(ValidationError::UnknownQueueUnbounded { transition: 2 })
+        );
+    }
+}